@@ -0,0 +1,40 @@
+extern crate restartables;
+use restartables::{Failure, Restartable};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_util::sync::CancellationToken;
+
+// A Future that yields a random u16 when it resolves.
+struct RandomNum {}
+impl Future for RandomNum {
+    type Output = u16;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        cx.waker().wake_by_ref();
+        Poll::Ready(rand::random())
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    // Cancel the token when the user presses Ctrl-C, like an app shutting down would.
+    let token = CancellationToken::new();
+    let shutdown = token.clone();
+    tokio::spawn(async move {
+        tokio::signal::ctrl_c().await.unwrap();
+        shutdown.cancel();
+    });
+
+    // This test can never pass, and there's no timeout, so only cancellation can stop it.
+    let test_never_passes = |num: u16| -> Result<u16, u16> { Err(num) };
+
+    let factory = || RandomNum {};
+    let retrying =
+        Restartable::new(factory, None, test_never_passes).with_cancellation_token(token);
+
+    println!("Retrying forever, press Ctrl-C to stop");
+    match retrying.await {
+        Err(Failure::Cancelled { restarts }) => println!("Cancelled after {} restarts", restarts),
+        other => println!("{:?}", other),
+    }
+}
@@ -0,0 +1,52 @@
+extern crate restartables;
+use restartables::grpc;
+use std::time::Duration;
+use tonic::transport::{Channel, Server};
+use tonic_health::pb::health_client::HealthClient;
+use tonic_health::pb::HealthCheckRequest;
+
+const ADDR: &str = "127.0.0.1:50051";
+
+#[tokio::main]
+async fn main() {
+    // Start a gRPC health service, but only after a second.
+    tokio::spawn(async {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let (_reporter, service) = tonic_health::server::health_reporter();
+        println!("Server starting");
+        Server::builder()
+            .add_service(service)
+            .serve(ADDR.parse().unwrap())
+            .await
+            .unwrap();
+    });
+
+    // A lazy channel doesn't connect until the first call, so calls made before the server is up
+    // fail with `Unavailable`, which gets retried.
+    let channel = Channel::from_shared(format!("http://{}", ADDR))
+        .unwrap()
+        .connect_lazy();
+    let client = HealthClient::new(channel);
+    let outcome = grpc::execute(
+        || {
+            let mut client = client.clone();
+            async move {
+                let response = client.check(HealthCheckRequest::default()).await;
+                if let Err(status) = &response {
+                    println!("Call failed: {:?}", status.code());
+                }
+                response
+            }
+        },
+        Some(Duration::from_secs(5)),
+    )
+    .min_retry_interval(Duration::from_millis(100));
+    match outcome.await {
+        Ok(success) => println!(
+            "Server is {:?}, after {} restarts",
+            success.value.into_inner().status(),
+            success.restarts
+        ),
+        Err(failure) => println!("Gave up: {}", failure),
+    }
+}
@@ -0,0 +1,69 @@
+extern crate restartables;
+use restartables::{Failure, Restartable};
+use std::time::{Duration, Instant};
+
+// A service which takes a random amount of time to become healthy after starting up.
+struct Service {
+    healthy_at: Instant,
+}
+
+impl Service {
+    fn start() -> Self {
+        let startup = Duration::from_millis(rand::random::<u64>() % 3000);
+        Service {
+            healthy_at: Instant::now() + startup,
+        }
+    }
+
+    async fn check_health(&self) -> Result<(), &'static str> {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        if Instant::now() >= self.healthy_at {
+            Ok(())
+        } else {
+            Err("still starting up")
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let service = std::sync::Arc::new(Service::start());
+
+    // Check the service's health in the background, every 100ms, until it's healthy.
+    let checker = Restartable::from_try_future(
+        move || {
+            let service = service.clone();
+            async move { service.check_health().await }
+        },
+        None,
+    )
+    .gate_next_attempt(|| tokio::time::sleep(Duration::from_millis(100)))
+    .spawn_on(&tokio::runtime::Handle::current());
+
+    // Meanwhile, report on its progress, and give up if the service takes too long.
+    let give_up_at = Instant::now() + Duration::from_secs(2);
+    while !checker.is_finished() {
+        if Instant::now() >= give_up_at {
+            println!("Giving up on the service");
+            checker.cancel();
+            break;
+        }
+        let metrics = checker.metrics();
+        println!(
+            "{} health checks so far, {} failed",
+            metrics.attempts, metrics.failures
+        );
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+
+    match checker.await.unwrap() {
+        Ok(success) => println!(
+            "Service was healthy after {:?} and {} failed checks",
+            success.duration, success.restarts
+        ),
+        Err(Failure::Cancelled { restarts }) => {
+            println!("Stopped checking after {} restarts", restarts)
+        }
+        Err(failure) => println!("Health check failed: {}", failure),
+    }
+}
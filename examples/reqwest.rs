@@ -1,11 +1,11 @@
 extern crate restartables;
-use reqwest;
 use reqwest::Method;
 use restartables::Restartable;
 use std::default::Default;
 use std::time::Duration;
 
 #[derive(Debug)]
+#[allow(dead_code)]
 enum MyError {
     Reqwest(reqwest::Error),
     BadStatus(reqwest::StatusCode),
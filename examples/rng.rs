@@ -37,9 +37,18 @@ async fn main() {
             success.duration.as_micros(),
             success.restarts
         ),
-        Err(Failure::Timeout) => println!("Never found an even number :("),
-        Err(Failure::Err { error, restarts }) => {
-            println!("Error {} after {} restarts", error, restarts)
-        }
+        Err(Failure::Timeout { .. }) => println!("Never found an even number :("),
+        Err(Failure::Err {
+            error,
+            restarts,
+            elapsed,
+            ..
+        }) => println!(
+            "Error {} after {} restarts and {}us",
+            error,
+            restarts,
+            elapsed.as_micros()
+        ),
+        Err(failure) => println!("Stopped by {:?}", failure.reason()),
     };
 }
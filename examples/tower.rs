@@ -0,0 +1,67 @@
+extern crate restartables;
+use hyper::{Body, Client, Request, Response, StatusCode, Uri};
+use restartables::tower::RetryLayer;
+use restartables::{FixedInterval, RetryPolicy};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tower::{ServiceBuilder, ServiceExt};
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum MyError {
+    Hyper(hyper::Error),
+    BadStatus(StatusCode),
+}
+
+#[tokio::main]
+async fn main() {
+    let uri = serve_flaky().await;
+
+    // Retry unsuccessful statuses and connection errors, for up to two seconds, waiting 100ms
+    // between attempts.
+    let policy = RetryPolicy::builder()
+        .timeout(Duration::from_secs(2))
+        .backoff(FixedInterval(Duration::from_millis(100)))
+        .build();
+    let service = ServiceBuilder::new()
+        .layer(RetryLayer::new(
+            policy,
+            |resp: hyper::Result<Response<Body>>| match resp {
+                Ok(resp) if resp.status().is_success() => Ok(resp.status()),
+                Ok(resp) => Err(MyError::BadStatus(resp.status())),
+                Err(e) => Err(MyError::Hyper(e)),
+            },
+        ))
+        // hyper's requests can't be cloned, so retry the URI, and build each attempt's request.
+        .map_request(|uri: Uri| {
+            println!("GET {}", uri);
+            Request::get(uri).body(Body::empty()).unwrap()
+        })
+        .service(Client::new());
+    println!("{:?}", service.oneshot(uri).await);
+}
+
+/// Serves "503 Service Unavailable" to the first two requests, then "200 OK".
+async fn serve_flaky() -> Uri {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        for request in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            let status = if request < 2 {
+                "503 Service Unavailable"
+            } else {
+                "200 OK"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+        }
+    });
+    url.parse().unwrap()
+}
@@ -0,0 +1,137 @@
+use crate::Restartable;
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+
+/// Like [`Restartable::new`], but the test is async, for tests which have to do more I/O before
+/// they can decide, e.g. reading a response's body.
+///
+/// Each attempt runs its future, then the test's future on that output, and passes if the test's
+/// future resolves to `Ok`. The two run as one [`AsyncTested`] future, so the test's time counts
+/// towards the timeout, and towards any per-attempt timeout, just like the attempt's own.
+///
+/// ```
+/// use restartables::async_test;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Each attempt submits a job. Checking whether the job worked takes another round trip.
+/// let jobs = AtomicUsize::new(0);
+/// let submit = || async { jobs.fetch_add(1, Ordering::SeqCst) };
+/// let check = |job| async move {
+///     tokio::time::sleep(Duration::from_millis(1)).await;
+///     if job == 2 { Ok(job) } else { Err(job) }
+/// };
+/// let success = async_test(submit, Some(Duration::from_secs(1)), check).await.unwrap();
+/// assert_eq!(success.value, 2);
+/// assert!(success.attempt_durations.iter().all(|d| *d >= Duration::from_millis(1)));
+/// # }
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn async_test<Fut, Factory, Test, TestFut, T, E>(
+    factory: Factory,
+    timeout: Option<Duration>,
+    test: Test,
+) -> Restartable<
+    AsyncTested<Fut, Test, TestFut>,
+    fn(Result<T, E>) -> Result<T, E>,
+    impl Fn() -> AsyncTested<Fut, Test, TestFut>,
+    T,
+    E,
+>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output) -> TestFut,
+    TestFut: Future<Output = Result<T, E>>,
+{
+    let test = Arc::new(test);
+    let factory = move || AsyncTested {
+        stage: Stage::Running(factory()),
+        test: test.clone(),
+    };
+    Restartable::from_try_future(factory, timeout)
+}
+
+/// One attempt from [`async_test`]: the attempt's future, followed by the test's future on its
+/// output.
+#[pin_project]
+pub struct AsyncTested<Fut, Test, TestFut> {
+    #[pin]
+    stage: Stage<Fut, TestFut>,
+    test: Arc<Test>,
+}
+
+#[pin_project(project = StageProj)]
+enum Stage<Fut, TestFut> {
+    Running(#[pin] Fut),
+    Testing(#[pin] TestFut),
+}
+
+impl<Fut, Test, TestFut> Future for AsyncTested<Fut, Test, TestFut>
+where
+    Fut: Future,
+    Test: Fn(Fut::Output) -> TestFut,
+    TestFut: Future,
+{
+    type Output = TestFut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            match this.stage.as_mut().project() {
+                StageProj::Running(fut) => {
+                    let output = ready!(fut.poll(cx));
+                    let testing = (this.test)(output);
+                    this.stage.set(Stage::Testing(testing));
+                }
+                StageProj::Testing(testing) => return testing.poll(cx),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Failure, TimeoutScope};
+
+    /// A test which never decides, but keeps its task awake, so the timeouts get checked.
+    async fn never_decides() -> Result<(), ()> {
+        loop {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn a_test_which_never_finishes_times_out() {
+        let never = |()| never_decides();
+        let outcome = async_test(|| async {}, Some(Duration::from_millis(10)), never).await;
+        match outcome {
+            Err(Failure::Timeout { restarts, .. }) => assert_eq!(restarts, 0),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn tests_use_up_the_attempt_timeout() {
+        let outcome = async_test(|| async {}, None, |()| never_decides())
+            .max_restarts(1)
+            .attempt_timeout_fn(|_| Duration::from_millis(5))
+            .await;
+        match outcome {
+            Err(Failure::Timeout {
+                restarts, scope, ..
+            }) => {
+                assert_eq!(restarts, 1);
+                assert_eq!(scope, TimeoutScope::Attempt);
+            }
+            other => panic!("expected an attempt timeout, got {:?}", other),
+        }
+    }
+}
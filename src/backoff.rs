@@ -0,0 +1,430 @@
+use crate::{Resolve, Restartable};
+use std::future::Future;
+use std::time::Duration;
+
+/// How long to wait after a failed attempt, before starting the next one.
+///
+/// Set one with [`Restartable::backoff`] or [`Restartable::with_backoff`]. Without one, a failed
+/// attempt is restarted straight away, which hammers whatever the attempts call as fast as the
+/// executor polls. A `Duration` is a backoff which always waits that long, like
+/// [`FixedInterval`].
+///
+/// A backoff can also end the loop, by returning `None` from [`next_delay`](Backoff::next_delay).
+/// Then the loop gives up instead of restarting, with [`Failure::Exhausted`] if the attempt failed
+/// the test, or [`Failure::Timeout`] if it was abandoned. [`Delays`] does this once it runs out of
+/// delays.
+///
+/// [`Failure::Exhausted`]: crate::Failure::Exhausted
+/// [`Failure::Timeout`]: crate::Failure::Timeout
+pub trait Backoff {
+    /// The delay after the attempt which just failed. `restarts` is how many earlier attempts
+    /// failed, so it's 0 after the first attempt fails, 1 after the second, and so on. A loop
+    /// made with [`Restartable::resume`] carries on from its saved
+    /// [`RetryState::backoff_position`](crate::RetryState::backoff_position).
+    fn delay(&mut self, restarts: usize) -> Duration;
+
+    /// Like [`delay`](Backoff::delay), or `None` to stop retrying. This is what a Restartable
+    /// calls. By default, it never stops.
+    fn next_delay(&mut self, restarts: usize) -> Option<Duration> {
+        Some(self.delay(restarts))
+    }
+
+    /// Whether [`next_delay`](Backoff::next_delay) ever returns `None`, so this backoff stops the
+    /// loop by itself. [`Restartable::validate`] doesn't count a loop with such a backoff as
+    /// unbounded. By default, it's `false`, so override it along with `next_delay`.
+    fn can_stop(&self) -> bool {
+        false
+    }
+
+    /// The longest a loop with this backoff could spend waiting between `max_attempts` attempts,
+    /// for budgeting a timeout. That's the sum of the delays after every attempt but the last, or
+    /// fewer if the backoff stops first. `None` for unlimited attempts means the backoff has to
+    /// stop by itself, otherwise the total is unbounded, and this returns `None`. It also returns
+    /// `None` if the delays are random, like [`Jittered`](crate::Jittered)'s.
+    ///
+    /// This works the delays out on a clone of the backoff, so it takes time proportional to
+    /// `max_attempts`.
+    ///
+    /// ```
+    /// use restartables::{Backoff, Delays, ExponentialBackoff};
+    /// use std::time::Duration;
+    ///
+    /// let exponential = ExponentialBackoff {
+    ///     base: Duration::from_millis(100),
+    ///     factor: 2.0,
+    ///     max: None,
+    /// };
+    /// // Waits 100ms, 200ms and 400ms between four attempts.
+    /// assert_eq!(exponential.worst_case_total(Some(4)), Some(Duration::from_millis(700)));
+    /// assert_eq!(exponential.worst_case_total(None), None);
+    ///
+    /// let schedule = Delays(vec![1, 5, 10].into_iter().map(Duration::from_secs));
+    /// assert_eq!(schedule.worst_case_total(None), Some(Duration::from_secs(16)));
+    /// ```
+    fn worst_case_total(&self, max_attempts: Option<usize>) -> Option<Duration>
+    where
+        Self: Clone + Sized,
+    {
+        if max_attempts.is_none() && !self.can_stop() {
+            return None;
+        }
+        let mut backoff = self.clone();
+        let mut total = Duration::ZERO;
+        let backoffs = max_attempts.map_or(usize::MAX, |n| n.saturating_sub(1));
+        for restarts in 0..backoffs {
+            match backoff.next_delay(restarts) {
+                Some(delay) => total = total.saturating_add(delay),
+                None => break,
+            }
+        }
+        Some(total)
+    }
+}
+
+impl Backoff for Duration {
+    fn delay(&mut self, _restarts: usize) -> Duration {
+        *self
+    }
+}
+
+/// A [`Backoff`] which always waits for the same interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FixedInterval(pub Duration);
+
+impl Backoff for FixedInterval {
+    fn delay(&mut self, _restarts: usize) -> Duration {
+        self.0
+    }
+}
+
+/// A [`Backoff`] which never waits, the same as having no backoff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NoBackoff;
+
+impl Backoff for NoBackoff {
+    fn delay(&mut self, _restarts: usize) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// A [`Backoff`] which takes its delays from an iterator, one per failure, and stops retrying
+/// once the iterator runs out.
+///
+/// This makes a hand-written schedule, or any iterator adaptor chain, into a backoff. A finite
+/// iterator also limits how many times the loop restarts, as long as its
+/// [`size_hint`](Iterator::size_hint) has an upper bound, which e.g. a `Vec`'s, or anything
+/// after [`take`](Iterator::take), does.
+///
+/// ```
+/// use restartables::{Delays, Failure, Restartable};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let schedule = vec![1, 5, 10].into_iter().map(Duration::from_millis);
+/// let retrying = Restartable::new(|| async { 1 }, None, |n: u8| Err::<(), _>(n))
+///     .backoff(Delays(schedule));
+/// match retrying.await {
+///     Err(Failure::Exhausted { restarts, .. }) => assert_eq!(restarts, 3),
+///     other => panic!("expected the schedule to run out, got {:?}", other),
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Delays<I>(pub I);
+
+impl<I: Iterator<Item = Duration>> Delays<I> {
+    /// Takes the delays from anything which can be iterated over, e.g. an array, a `Vec`, or an
+    /// iterator like [`std::iter::repeat`].
+    ///
+    /// ```
+    /// use restartables::{Backoff, Delays};
+    /// use std::time::Duration;
+    ///
+    /// // A fixed schedule, which stops retrying after three failures.
+    /// let schedule = Delays::new([100, 200, 500].map(Duration::from_millis));
+    /// assert_eq!(schedule.worst_case_total(None), Some(Duration::from_millis(800)));
+    ///
+    /// // A schedule from a slice.
+    /// let delays = [Duration::from_millis(100), Duration::from_millis(200)];
+    /// let mut from_slice = Delays::new(delays.iter().copied());
+    /// assert_eq!(from_slice.next_delay(0), Some(Duration::from_millis(100)));
+    ///
+    /// // The same delay forever, which never stops retrying by itself.
+    /// let endless = Delays::new(std::iter::repeat(Duration::from_millis(100)));
+    /// assert!(!endless.can_stop());
+    /// ```
+    pub fn new<D>(delays: D) -> Self
+    where
+        D: IntoIterator<IntoIter = I>,
+    {
+        Delays(delays.into_iter())
+    }
+}
+
+impl<I: Iterator<Item = Duration>> Backoff for Delays<I> {
+    /// The iterator's next delay, or no delay once it's run out. A Restartable stops instead.
+    fn delay(&mut self, restarts: usize) -> Duration {
+        self.next_delay(restarts).unwrap_or(Duration::ZERO)
+    }
+
+    fn next_delay(&mut self, _restarts: usize) -> Option<Duration> {
+        self.0.next()
+    }
+
+    /// Whether the iterator says it's finite, from its [`size_hint`](Iterator::size_hint). An
+    /// endless one, like [`std::iter::repeat`], never stops the loop.
+    fn can_stop(&self) -> bool {
+        self.0.size_hint().1.is_some()
+    }
+}
+
+/// A [`Backoff`] which multiplies the delay by `factor` after every failure: `base`, then
+/// `base * factor`, then `base * factor^2`, and so on, up to `max`.
+///
+/// ```
+/// use restartables::{Backoff, ExponentialBackoff};
+/// use std::time::Duration;
+///
+/// let mut backoff = ExponentialBackoff {
+///     base: Duration::from_millis(100),
+///     factor: 2.0,
+///     max: Some(Duration::from_millis(500)),
+/// };
+/// let delays: Vec<_> = (0..5).map(|restarts| backoff.delay(restarts).as_millis()).collect();
+/// assert_eq!(delays, [100, 200, 400, 500, 500]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExponentialBackoff {
+    /// The delay after the first failure
+    pub base: Duration,
+    /// What each delay is multiplied by to get the next one. This should be at least 1.
+    pub factor: f64,
+    /// The longest delay, if there is one. Without it, the delays keep growing, up to
+    /// `Duration::MAX`.
+    pub max: Option<Duration>,
+}
+
+impl Backoff for ExponentialBackoff {
+    fn delay(&mut self, restarts: usize) -> Duration {
+        let secs = self.base.as_secs_f64() * self.factor.powf(restarts as f64);
+        let delay = if secs.is_nan() || secs <= 0.0 {
+            Duration::ZERO
+        } else {
+            // Too big for a Duration, even if it's infinite.
+            Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX)
+        };
+        match self.max {
+            Some(max) => delay.min(max),
+            None => delay,
+        }
+    }
+}
+
+impl<Fut, Test, Factory, T, E> Restartable<Fut, Test, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+{
+    /// Like [`Restartable::new`], but waits according to `backoff` after every failed attempt.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let attempts = AtomicUsize::new(0);
+    /// let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    /// let start = Instant::now();
+    /// let success = Restartable::with_backoff(
+    ///     factory,
+    ///     Some(Duration::from_secs(1)),
+    ///     |n| if n == 3 { Ok(n) } else { Err(n) },
+    ///     Duration::from_millis(10),
+    /// )
+    /// .await
+    /// .unwrap();
+    /// assert_eq!(success.restarts, 3);
+    /// assert!(start.elapsed() >= Duration::from_millis(30));
+    /// # }
+    /// ```
+    pub fn with_backoff<B>(
+        factory: Factory,
+        timeout: Option<Duration>,
+        test: Test,
+        backoff: B,
+    ) -> Self
+    where
+        B: Backoff + Send + Sync + 'static,
+    {
+        Self::new(factory, timeout, test).backoff(backoff)
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// After every failed attempt, wait for as long as `backoff` says before starting the next.
+    ///
+    /// An attempt fails if it fails the test, or is abandoned because it ran past its timeout or
+    /// [`max_pending_polls`](Restartable::max_pending_polls). The wait counts towards the
+    /// timeout, and is cut short so it never runs past the timeout or
+    /// [`deadline`](Restartable::deadline). If there's also a
+    /// [`min_retry_interval`](Restartable::min_retry_interval), the longer of the two wins. The
+    /// wait uses the same timer as `min_retry_interval`. If the backoff says to stop, the loop
+    /// gives up instead of restarting.
+    pub fn backoff<B>(mut self, backoff: B) -> Self
+    where
+        B: Backoff + Send + Sync + 'static,
+    {
+        self.backoff = Some(Box::new(backoff));
+        self
+    }
+
+    /// After an attempt fails the test, wait according to the backoff which `backoff_for` picks
+    /// for its error, instead of the one from [`backoff`](Restartable::backoff).
+    ///
+    /// This lets the wait fit the failure, e.g. a long one after being rate limited, but a short
+    /// one after a dropped connection. `backoff_for` is called after every failure, and the
+    /// backoff it returns is asked for the delay at the loop's backoff position, just like
+    /// `backoff` would be, so an [`ExponentialBackoff`] still grows from one failure to the next.
+    /// The backoff is then dropped, so one which keeps its own state, like [`Delays`], starts
+    /// again every time. Attempts which are abandoned, so have no error, still use `backoff`.
+    ///
+    /// ```
+    /// use restartables::{ExponentialBackoff, Restartable};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// #[derive(Debug)]
+    /// enum FetchError {
+    ///     Status(u16),
+    ///     ConnectionReset,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // The connection drops, then the server is unavailable, then it rate limits us.
+    /// let calls = AtomicUsize::new(0);
+    /// let fetch = || async {
+    ///     match calls.fetch_add(1, Ordering::SeqCst) {
+    ///         0 => Err(FetchError::ConnectionReset),
+    ///         1 => Err(FetchError::Status(503)),
+    ///         2 => Err(FetchError::Status(429)),
+    ///         _ => Ok("body"),
+    ///     }
+    /// };
+    /// let start = Instant::now();
+    /// let success = Restartable::new(fetch, Some(Duration::from_secs(5)), |result| result)
+    ///     .backoff_for(|error| {
+    ///         let base = match error {
+    ///             FetchError::Status(429) => Duration::from_millis(100),
+    ///             FetchError::Status(_) => Duration::from_millis(20),
+    ///             FetchError::ConnectionReset => Duration::from_millis(1),
+    ///         };
+    ///         ExponentialBackoff {
+    ///             base,
+    ///             factor: 2.0,
+    ///             max: Some(Duration::from_secs(1)),
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(success.value, "body");
+    /// // 1ms, then 20ms doubled once, then 100ms doubled twice.
+    /// assert!(start.elapsed() >= Duration::from_millis(441));
+    /// # }
+    /// ```
+    pub fn backoff_for<F, B>(mut self, backoff_for: F) -> Self
+    where
+        F: Fn(&E) -> B + Send + Sync + 'static,
+        B: Backoff,
+    {
+        self.backoff_for = Some(Box::new(move |error, position| {
+            backoff_for(error).next_delay(position)
+        }));
+        self
+    }
+
+    /// Once `n` attempts in a row pass the test, start the backoff again from its first delay,
+    /// so a flappy service's next failure waits for the base delay, not the longest one.
+    ///
+    /// This only matters when passing attempts are restarted, i.e. under
+    /// [`require_success_rate`](Restartable::require_success_rate), or when the test returns
+    /// [`Resolution::AcceptButContinue`](crate::Resolution::AcceptButContinue). Any failed or
+    /// abandoned attempt starts the count again. Resetting the backoff doesn't give back any of
+    /// the time it's already waited, which still counts towards the timeout.
+    ///
+    /// ```
+    /// use restartables::{ExponentialBackoff, Restartable};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // A health check which flaps: two failures, then two passes, and so on.
+    /// let checks = AtomicUsize::new(0);
+    /// let check = || async { checks.fetch_add(1, Ordering::SeqCst) };
+    /// let healthy = |n: usize| if n % 4 < 2 { Err(n) } else { Ok(n) };
+    /// let backoff = ExponentialBackoff {
+    ///     base: Duration::from_millis(1),
+    ///     factor: 2.0,
+    ///     max: None,
+    /// };
+    /// let success = Restartable::new(check, Some(Duration::from_secs(5)), healthy)
+    ///     .require_success_rate(8, 0.5)
+    ///     .backoff(backoff)
+    ///     .reset_backoff_after_success(2)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(success.value, 7);
+    /// # }
+    /// ```
+    pub fn reset_backoff_after_success(mut self, n: usize) -> Self {
+        self.reset_backoff_after = Some(n);
+        self
+    }
+
+    /// Never wait for more than `fraction` of the time left until the timeout or
+    /// [`deadline`](Restartable::deadline) between attempts, so the backoff can't use up the whole
+    /// budget and leave no time for a last attempt.
+    ///
+    /// This caps every wait, whether it's from the [`backoff`](Restartable::backoff) or
+    /// [`min_retry_interval`](Restartable::min_retry_interval), against the time left when the
+    /// wait starts. `fraction` is clamped to between 0 and 1, and a NaN `fraction` is treated as
+    /// 1, which is the same as not setting it. Without a timeout or deadline, this does nothing.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let attempts = AtomicUsize::new(0);
+    /// let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    /// let start = Instant::now();
+    /// let retrying = Restartable::new(factory, Some(Duration::from_millis(100)), |n| Err::<(), _>(n))
+    ///     .backoff(Duration::from_secs(60))
+    ///     .max_backoff_fraction(0.5);
+    /// assert!(retrying.await.is_err());
+    /// // Each wait only took half the time left, so there was time for more attempts.
+    /// assert!(attempts.load(Ordering::SeqCst) >= 3);
+    /// assert!(start.elapsed() < Duration::from_secs(60));
+    /// # }
+    /// ```
+    pub fn max_backoff_fraction(mut self, fraction: f64) -> Self {
+        self.max_backoff_fraction = Some(if fraction.is_nan() {
+            1.0
+        } else {
+            fraction.clamp(0.0, 1.0)
+        });
+        self
+    }
+}
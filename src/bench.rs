@@ -0,0 +1,153 @@
+use crate::{MetricsSink, Restartable};
+use std::future::Future;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Latency statistics from [`benchmark`].
+///
+/// Every statistic is zero if there were no measured runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BenchSuccess {
+    /// How many runs were measured, not counting warmup runs
+    pub runs: usize,
+    /// The fastest measured run
+    pub min: Duration,
+    /// The slowest measured run
+    pub max: Duration,
+    /// The mean of the measured runs
+    pub mean: Duration,
+    /// The median measured run
+    pub p50: Duration,
+    /// The 95th percentile measured run
+    pub p95: Duration,
+    /// How long the whole benchmark took, including warmup runs
+    pub duration: Duration,
+}
+
+/// Runs `factory`'s futures `warmup + runs` times, one after another, and resolves to latency
+/// statistics for the last `runs` of them.
+///
+/// This uses the retry machinery as a quick micro-benchmark harness for a future. Every attempt
+/// runs to completion, and its output is ignored, so there's no test and no timeout. The first
+/// `warmup` attempts are run but not measured, so that caches, connection pools and the like are
+/// warm by the time measuring starts. Each run is timed from its first poll until it resolves.
+///
+/// ```
+/// use restartables::benchmark;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let calls = AtomicUsize::new(0);
+/// let factory = || async {
+///     calls.fetch_add(1, Ordering::SeqCst);
+///     tokio::time::sleep(Duration::from_millis(1)).await;
+/// };
+/// let stats = benchmark(factory, 2, 10).await;
+/// assert_eq!(calls.load(Ordering::SeqCst), 12);
+/// assert_eq!(stats.runs, 10);
+/// assert!(stats.min >= Duration::from_millis(1));
+/// assert!(stats.min <= stats.p50 && stats.p50 <= stats.p95 && stats.p95 <= stats.max);
+/// # }
+/// ```
+pub async fn benchmark<Fut, Factory>(factory: Factory, warmup: usize, runs: usize) -> BenchSuccess
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+{
+    let start = Instant::now();
+    let attempts = warmup.saturating_add(runs);
+    let durations = Durations::default();
+    if attempts > 0 {
+        // Fail every attempt, so the loop only stops once every run is done.
+        let ignore = |_| Err::<(), ()>(());
+        // Unlike `retry_n`, a loop with a `None` timeout is still timed, so every attempt's
+        // duration gets measured. Global defaults are ignored, since they'd cut runs short.
+        let mut restartable =
+            Restartable::from_parts(factory, None, ignore).metrics(durations.clone());
+        restartable.max_attempts = Some(attempts);
+        let outcome = restartable.await;
+        debug_assert!(outcome.is_err(), "every attempt fails the test");
+    }
+    let mut durations = durations.take();
+    let measured = &mut durations[warmup.min(attempts)..];
+    measured.sort_unstable();
+    stats(measured, start.elapsed())
+}
+
+/// Builds the statistics from sorted durations.
+fn stats(sorted: &[Duration], duration: Duration) -> BenchSuccess {
+    let (min, max) = match (sorted.first(), sorted.last()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => (Duration::ZERO, Duration::ZERO),
+    };
+    let mean = match sorted.len() {
+        0 => Duration::ZERO,
+        n => {
+            let total: u128 = sorted.iter().map(Duration::as_nanos).sum();
+            Duration::from_nanos((total / n as u128) as u64)
+        }
+    };
+    BenchSuccess {
+        runs: sorted.len(),
+        min,
+        max,
+        mean,
+        p50: percentile(sorted, 50),
+        p95: percentile(sorted, 95),
+        duration,
+    }
+}
+
+/// The nearest-rank percentile of sorted durations.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Records how long every attempt took. Clones share the same records.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Durations(Arc<Mutex<Vec<Duration>>>);
+
+impl Durations {
+    pub(crate) fn take(&self) -> Vec<Duration> {
+        std::mem::take(&mut self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl<T, E, Ctx> MetricsSink<T, E, Ctx> for Durations {
+    fn on_attempt_end(&self, _: &Result<T, E>, duration: Duration, _: &Ctx) {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let sorted: Vec<_> = (1..=20).map(Duration::from_millis).collect();
+        let stats = stats(&sorted, Duration::ZERO);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.max, Duration::from_millis(20));
+        assert_eq!(stats.p50, Duration::from_millis(10));
+        assert_eq!(stats.p95, Duration::from_millis(19));
+        assert_eq!(stats.mean, Duration::from_micros(10_500));
+    }
+
+    #[test]
+    fn no_runs_gives_zeroes() {
+        let stats = stats(&[], Duration::ZERO);
+        assert_eq!(stats.runs, 0);
+        assert_eq!(stats.p95, Duration::ZERO);
+        assert_eq!(stats.mean, Duration::ZERO);
+    }
+}
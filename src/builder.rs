@@ -0,0 +1,146 @@
+use crate::{Backoff, Restartable};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// Configures a [`Restartable`] one named setting at a time, instead of with positional
+/// arguments.
+///
+/// A factory and a test are required. Until both are set, there's no `build` method, so
+/// forgetting one is a compile error rather than a runtime one. Every other setting is optional,
+/// and left unset, it's the same as with [`Restartable::new`].
+///
+/// ```
+/// use restartables::{ExponentialBackoff, Failure, RestartableBuilder};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let retrying = RestartableBuilder::new()
+///     .factory(|| async { 3 })
+///     .test(|n: u8| if n % 2 == 0 { Ok(n) } else { Err(n) })
+///     .timeout(Duration::from_secs(1))
+///     .backoff(ExponentialBackoff {
+///         base: Duration::from_millis(1),
+///         factor: 2.0,
+///         max: None,
+///     })
+///     .max_restarts(2)
+///     .build();
+/// match retrying.await {
+///     Err(Failure::MaxRetries { error, restarts }) => {
+///         assert_eq!(error, 3);
+///         assert_eq!(restarts, 2);
+///     }
+///     other => panic!("expected to hit the retry cap, got {:?}", other),
+/// }
+/// # }
+/// ```
+///
+/// Without a test, there's nothing to build:
+///
+/// ```compile_fail
+/// use restartables::RestartableBuilder;
+///
+/// let retrying = RestartableBuilder::new().factory(|| async { 3 }).build();
+/// ```
+pub struct RestartableBuilder<Factory = (), Test = ()> {
+    factory: Factory,
+    test: Test,
+    timeout: Option<Duration>,
+    backoff: Option<Box<dyn Backoff + Send + Sync>>,
+    max_restarts: Option<usize>,
+}
+
+impl RestartableBuilder {
+    /// Starts configuring a `Restartable`, with no factory or test yet.
+    pub fn new() -> Self {
+        RestartableBuilder {
+            factory: (),
+            test: (),
+            timeout: None,
+            backoff: None,
+            max_restarts: None,
+        }
+    }
+}
+
+impl Default for RestartableBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Factory, Test> RestartableBuilder<Factory, Test> {
+    /// Makes the futures to retry. See [`Restartable::new`].
+    pub fn factory<F>(self, factory: F) -> RestartableBuilder<F, Test> {
+        RestartableBuilder {
+            factory,
+            test: self.test,
+            timeout: self.timeout,
+            backoff: self.backoff,
+            max_restarts: self.max_restarts,
+        }
+    }
+
+    /// Decides whether each attempt's output passes. See [`Restartable::new`].
+    pub fn test<F>(self, test: F) -> RestartableBuilder<Factory, F> {
+        RestartableBuilder {
+            factory: self.factory,
+            test,
+            timeout: self.timeout,
+            backoff: self.backoff,
+            max_restarts: self.max_restarts,
+        }
+    }
+
+    /// The overall timeout for the retry loop. Defaults to no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Restartable::backoff`]. Defaults to no backoff.
+    pub fn backoff<B>(mut self, backoff: B) -> Self
+    where
+        B: Backoff + Send + Sync + 'static,
+    {
+        self.backoff = Some(Box::new(backoff));
+        self
+    }
+
+    /// See [`Restartable::max_restarts`]. Defaults to no limit.
+    pub fn max_restarts(mut self, n: usize) -> Self {
+        self.max_restarts = Some(n);
+        self
+    }
+}
+
+impl<Fut, Factory, Test, T, E> RestartableBuilder<Factory, Test>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+{
+    /// Makes the configured `Restartable`.
+    pub fn build(self) -> Restartable<Fut, Test, Factory, T, E> {
+        let mut restartable = Restartable::new(self.factory, self.timeout, self.test);
+        if let Some(backoff) = self.backoff {
+            restartable.backoff = Some(backoff);
+        }
+        if let Some(n) = self.max_restarts {
+            restartable = restartable.max_restarts(n);
+        }
+        restartable
+    }
+}
+
+impl<Factory, Test> fmt::Debug for RestartableBuilder<Factory, Test> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestartableBuilder")
+            .field("timeout", &self.timeout)
+            .field("backoff", &self.backoff.is_some())
+            .field("max_restarts", &self.max_restarts)
+            .finish()
+    }
+}
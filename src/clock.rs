@@ -0,0 +1,22 @@
+use std::time::Instant;
+
+/// Where a [`Restartable`](crate::Restartable) gets the current time from, for its timeout,
+/// deadline, per-attempt timeouts and reported durations. See
+/// [`Restartable::clock`](crate::Restartable::clock).
+///
+/// Every `Restartable` uses [`SystemClock`] unless told otherwise. Swapping in a fake clock makes
+/// timing tests deterministic: advance it by hand, and the loop sees exactly that much time pass.
+pub trait Clock {
+    /// The current time.
+    fn now(&self) -> Instant;
+}
+
+/// The real system clock, from [`Instant::now`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
@@ -0,0 +1,76 @@
+use crate::bench::Durations;
+use crate::{Restartable, Success};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Keeps retrying until `timeout` expires, and resolves to every value which passed the test.
+///
+/// This inverts the usual resolution condition: instead of stopping at the first value which
+/// passes, every attempt's output is run through `test`, passing values are collected, and a fresh
+/// attempt is started whether it passed or not. It's for sampling or polling over a window. It
+/// always resolves to a `Success`, which is empty if no value passed. `restarts` counts every
+/// attempt after the first, and values are in the order their attempts finished.
+///
+/// Like any `Restartable`, the timeout is only checked when polled, so an attempt which is pending
+/// when the timeout expires is dropped, and its value is never collected.
+///
+/// ```
+/// use restartables::collect_until_timeout;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let samples = AtomicU32::new(0);
+/// let sample = || async {
+///     tokio::time::sleep(Duration::from_millis(1)).await;
+///     samples.fetch_add(1, Ordering::SeqCst)
+/// };
+/// let even = |n: u32| if n % 2 == 0 { Ok(n) } else { Err(n) };
+/// let collected = collect_until_timeout(sample, Duration::from_millis(50), even).await;
+/// assert!(!collected.value.is_empty());
+/// assert!(collected.value.iter().all(|n| n % 2 == 0));
+/// assert!(collected.duration >= Duration::from_millis(50));
+/// # }
+/// ```
+pub async fn collect_until_timeout<Fut, Test, Factory, T, E>(
+    factory: Factory,
+    timeout: Duration,
+    test: Test,
+) -> Success<Vec<T>>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+{
+    let start = Instant::now();
+    let collected = Mutex::new(Vec::new());
+    // Fail every attempt, so the loop only stops when the timeout expires.
+    let collect = |output| {
+        if let Ok(value) = test(output) {
+            collected
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(value);
+        }
+        Err::<(), ()>(())
+    };
+    let durations = Durations::default();
+    // Global defaults are ignored, since a limit on attempts would end the window early.
+    let restartable =
+        Restartable::from_parts(factory, Some(timeout), collect).metrics(durations.clone());
+    let restarts = match restartable.await {
+        Ok(_) => unreachable!("every attempt fails the test"),
+        Err(failure) => failure.restarts(),
+    };
+    Success {
+        value: collected
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        duration: start.elapsed(),
+        restarts,
+        raw_output: None,
+        attempt_durations: durations.take(),
+    }
+}
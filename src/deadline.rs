@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+/// A point in time which a retry loop must finish by, shareable between nested loops.
+///
+/// Pass the same deadline to an outer `Restartable`, and to every inner `Restartable` its factory
+/// builds, with [`Restartable::deadline`](crate::Restartable::deadline). Then the inner loops
+/// stop when the outer loop's time runs out, instead of blowing the total budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline(Instant);
+
+impl Deadline {
+    /// A deadline `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Deadline(Instant::now() + timeout)
+    }
+
+    /// A deadline at `instant`.
+    pub fn at(instant: Instant) -> Self {
+        Deadline(instant)
+    }
+
+    /// When this deadline is.
+    pub fn instant(&self) -> Instant {
+        self.0
+    }
+
+    /// How long is left until this deadline, or zero if it's already passed.
+    pub fn remaining(&self) -> Duration {
+        self.remaining_at(Instant::now())
+    }
+
+    /// Whether this deadline has already passed.
+    pub fn has_passed(&self) -> bool {
+        self.has_passed_at(Instant::now())
+    }
+
+    /// How long is left until this deadline as of `now`, or zero if it had passed by then.
+    pub(crate) fn remaining_at(&self, now: Instant) -> Duration {
+        self.0.saturating_duration_since(now)
+    }
+
+    /// Whether this deadline had passed by `now`.
+    pub(crate) fn has_passed_at(&self, now: Instant) -> bool {
+        now > self.0
+    }
+}
@@ -0,0 +1,272 @@
+#[cfg(not(feature = "use_async_std"))]
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(not(feature = "use_async_std"))]
+use std::sync::{Condvar, Mutex, MutexGuard, OnceLock, PoisonError};
+#[cfg(not(feature = "use_async_std"))]
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+#[cfg(not(feature = "use_async_std"))]
+use std::time::Instant;
+
+/// A pending wait between attempts, for [`Restartable::min_retry_interval`] and
+/// [`Restartable::backoff`]. The Restartable's task sleeps until it's over, rather than waking
+/// itself to check the time.
+///
+/// [`Restartable::min_retry_interval`]: crate::Restartable::min_retry_interval
+/// [`Restartable::backoff`]: crate::Restartable::backoff
+pub(crate) type Delay = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// Makes the waits between attempts on some runtime's timer, so the rest of the Restartable
+/// doesn't depend on any one runtime.
+pub(crate) trait Sleeper {
+    fn sleep(&self, duration: Duration) -> Delay;
+}
+
+/// Waits with [`tokio::time::sleep`], so it follows tokio's clock, including paused time. Only
+/// used inside a tokio runtime, since tokio's timers panic outside one.
+#[cfg(feature = "use_tokio")]
+struct TokioSleeper;
+
+#[cfg(feature = "use_tokio")]
+impl Sleeper for TokioSleeper {
+    fn sleep(&self, duration: Duration) -> Delay {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Waits with [`async_std::task::sleep`], which doesn't need any particular runtime.
+#[cfg(feature = "use_async_std")]
+struct AsyncStdSleeper;
+
+#[cfg(feature = "use_async_std")]
+impl Sleeper for AsyncStdSleeper {
+    fn sleep(&self, duration: Duration) -> Delay {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
+
+/// Waits on one background timer thread, shared by every delay, which wakes the task when the
+/// time is up. This works on any executor.
+#[cfg(not(feature = "use_async_std"))]
+struct ThreadSleeper;
+
+#[cfg(not(feature = "use_async_std"))]
+impl Sleeper for ThreadSleeper {
+    fn sleep(&self, duration: Duration) -> Delay {
+        Box::pin(ThreadSleep {
+            duration,
+            registered: None,
+        })
+    }
+}
+
+/// The sleeper for outside a tokio runtime: async-std's, if it's enabled.
+#[cfg(feature = "use_async_std")]
+const FALLBACK: &dyn Sleeper = &AsyncStdSleeper;
+#[cfg(not(feature = "use_async_std"))]
+const FALLBACK: &dyn Sleeper = &ThreadSleeper;
+
+/// Waits for `duration` on the current runtime's timer. Features are additive, so having
+/// `use_tokio` on doesn't mean this is running on tokio: tokio's timer is only used if there's a
+/// tokio runtime to drive it.
+pub(crate) fn sleep(duration: Duration) -> Delay {
+    #[cfg(feature = "use_tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return TokioSleeper.sleep(duration);
+    }
+    FALLBACK.sleep(duration)
+}
+
+#[cfg(not(feature = "use_async_std"))]
+struct ThreadSleep {
+    duration: Duration,
+    /// Set on the first poll: when the sleep ends, and its entry in the timer's queue.
+    registered: Option<(Instant, u64)>,
+}
+
+#[cfg(not(feature = "use_async_std"))]
+impl Future for ThreadSleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.duration.is_zero() {
+            return Poll::Ready(());
+        }
+        let timer = Timer::get();
+        match self.registered {
+            None => {
+                let until = Instant::now() + self.duration;
+                self.registered = Some(timer.register(until, cx.waker()));
+                Poll::Pending
+            }
+            // The timer only forgets a sleep once it's over.
+            Some(registered) if timer.set_waker(registered, cx.waker()) => Poll::Pending,
+            Some(_) => Poll::Ready(()),
+        }
+    }
+}
+
+/// Cancels the sleep, so a dropped Restartable doesn't leave anything behind in the timer.
+#[cfg(not(feature = "use_async_std"))]
+impl Drop for ThreadSleep {
+    fn drop(&mut self) {
+        if let Some(registered) = self.registered {
+            Timer::get().cancel(registered);
+        }
+    }
+}
+
+/// The background thread behind every [`ThreadSleep`]. It's started by the first sleep, and
+/// wakes each sleep's task when its time is up.
+#[cfg(not(feature = "use_async_std"))]
+#[derive(Default)]
+struct Timer {
+    queue: Mutex<Queue>,
+    /// Notified when a sleep becomes the next one due, so the thread stops waiting for a later one.
+    changed: Condvar,
+}
+
+#[cfg(not(feature = "use_async_std"))]
+#[derive(Default)]
+struct Queue {
+    next_id: u64,
+    /// Keyed by when each sleep ends, then by when it was registered, so the first is the next due.
+    sleeps: BTreeMap<(Instant, u64), Waker>,
+}
+
+#[cfg(not(feature = "use_async_std"))]
+impl Timer {
+    fn get() -> &'static Timer {
+        static TIMER: OnceLock<Timer> = OnceLock::new();
+        let mut started = false;
+        let timer = TIMER.get_or_init(|| {
+            started = true;
+            Timer::default()
+        });
+        if started {
+            std::thread::Builder::new()
+                .name("restartables-timer".to_owned())
+                .spawn(move || timer.run())
+                .expect("couldn't start the timer thread");
+        }
+        timer
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a sleep which ends at `until`, returning its key.
+    fn register(&self, until: Instant, waker: &Waker) -> (Instant, u64) {
+        let mut queue = self.lock();
+        let key = (until, queue.next_id);
+        queue.next_id += 1;
+        queue.sleeps.insert(key, waker.clone());
+        if queue.sleeps.keys().next() == Some(&key) {
+            self.changed.notify_one();
+        }
+        key
+    }
+
+    /// Updates the waker for a sleep, returning `false` if it's already over.
+    fn set_waker(&self, key: (Instant, u64), waker: &Waker) -> bool {
+        match self.lock().sleeps.get_mut(&key) {
+            Some(existing) => {
+                if !existing.will_wake(waker) {
+                    *existing = waker.clone();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Forgets a sleep. Does nothing if it's already been woken.
+    fn cancel(&self, key: (Instant, u64)) {
+        self.lock().sleeps.remove(&key);
+    }
+
+    fn run(&self) {
+        let mut queue = self.lock();
+        loop {
+            let now = Instant::now();
+            let later = queue.sleeps.split_off(&(now, u64::MAX));
+            let due = std::mem::replace(&mut queue.sleeps, later);
+            if !due.is_empty() {
+                // Wake outside the lock, since waking can poll the sleep on this thread.
+                drop(queue);
+                due.into_values().for_each(Waker::wake);
+                queue = self.lock();
+                continue;
+            }
+            queue = match queue.sleeps.keys().next() {
+                Some(&(until, _)) => {
+                    let wait = until.saturating_duration_since(now);
+                    let waited = self.changed.wait_timeout(queue, wait);
+                    waited.unwrap_or_else(PoisonError::into_inner).0
+                }
+                None => self
+                    .changed
+                    .wait(queue)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "use_async_std")))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn dropping_a_sleep_cancels_it() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut sleep = ThreadSleep {
+            duration: Duration::from_secs(60),
+            registered: None,
+        };
+        assert!(Pin::new(&mut sleep).poll(&mut cx).is_pending());
+        let key = sleep.registered.unwrap();
+        assert!(Timer::get().lock().sleeps.contains_key(&key));
+        drop(sleep);
+        assert!(!Timer::get().lock().sleeps.contains_key(&key));
+    }
+
+    struct ThreadWaker(std::thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    #[test]
+    fn the_timer_thread_wakes_every_sleep() {
+        let waker = Waker::from(Arc::new(ThreadWaker(std::thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let start = Instant::now();
+        let mut sleeps: Vec<_> = (1..=20)
+            .map(|ms| sleep(Duration::from_millis(ms)))
+            .collect();
+        loop {
+            sleeps.retain_mut(|sleep| sleep.as_mut().poll(&mut cx).is_pending());
+            if sleeps.is_empty() {
+                break;
+            }
+            std::thread::park_timeout(Duration::from_secs(1));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,90 @@
+//! Panicking helpers for tests and prototypes.
+//!
+//! ```
+//! use restartables::expect::ExpectSuccess;
+//! use restartables::Restartable;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let retrying = Restartable::new(
+//!     || async { 4 },
+//!     Some(Duration::from_secs(1)),
+//!     |n: u8| if n % 2 == 0 { Ok(n) } else { Err("odd") },
+//! );
+//! let n = retrying.await.expect_success("should find an even number");
+//! assert_eq!(n, 4);
+//! # }
+//! ```
+
+use crate::Outcome;
+use std::fmt::Debug;
+
+/// Like [`Result::expect`], but the panic message describes how the retry loop failed.
+pub trait ExpectSuccess<T> {
+    /// Returns the value that passed the test.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the Restartable failed, with a message including `msg`, why the loop stopped,
+    /// how many restarts it made, how long it ran (if the failure records that), and the last
+    /// error from the test (if any).
+    fn expect_success(self, msg: &str) -> T;
+}
+
+impl<T, E: Debug, Raw> ExpectSuccess<T> for Outcome<T, E, Raw> {
+    #[track_caller]
+    fn expect_success(self, msg: &str) -> T {
+        match self {
+            Ok(success) => success.value,
+            Err(failure) => {
+                let elapsed = match failure.elapsed() {
+                    Some(elapsed) => format!(" in {:?}", elapsed),
+                    None => String::new(),
+                };
+                let stopped = failure.into_stopped();
+                match stopped.error {
+                    Some(error) => panic!(
+                        "{}: stopped by {:?} after {} restarts{}, last error: {:?}",
+                        msg, stopped.reason, stopped.restarts, elapsed, error
+                    ),
+                    None => panic!(
+                        "{}: stopped by {:?} after {} restarts{}",
+                        msg, stopped.reason, stopped.restarts, elapsed
+                    ),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Failure, TimeoutScope};
+    use std::time::Duration;
+
+    #[test]
+    #[should_panic(expected = "no luck: stopped by Timeout after 3 restarts in 20ms")]
+    fn panics_with_the_elapsed_time() {
+        let outcome: Outcome<(), ()> = Err(Failure::Timeout {
+            restarts: 3,
+            scope: TimeoutScope::Overall,
+            elapsed: Duration::from_millis(20),
+        });
+        outcome.expect_success("no luck");
+    }
+
+    #[test]
+    #[should_panic(expected = "no luck: stopped by Timeout after 2 restarts in 5ms, last error: 7")]
+    fn panics_with_the_elapsed_time_and_last_error() {
+        let outcome: Outcome<(), u8> = Err(Failure::Err {
+            error: 7,
+            restarts: 2,
+            elapsed: Duration::from_millis(5),
+            recent_errors: Vec::new(),
+            dropped_errors: 0,
+        });
+        outcome.expect_success("no luck");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::Restartable;
+use std::future::Future;
+use std::time::Duration;
+
+/// Builds a [`Restartable`] from a factory in a chained style, like the `futures` crate's
+/// `FutureExt`. This is implemented for every factory, i.e. every closure which makes futures.
+///
+/// ```
+/// use restartables::{Failure, RestartableExt};
+/// use std::future::Future;
+/// use std::pin::Pin;
+/// use std::task::{Context, Poll};
+/// use std::time::Duration;
+///
+/// // A Future that yields a random u16 when it resolves.
+/// struct RandomNum {}
+/// impl Future for RandomNum {
+///     type Output = u16;
+///     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+///         cx.waker().wake_by_ref();
+///         Poll::Ready(rand::random())
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let retrying = (|| RandomNum {}).restart_until(Some(Duration::from_millis(1)), |num| {
+///     if num % 2 == 0 {
+///         Ok(num)
+///     } else {
+///         Err("number wasn't even")
+///     }
+/// });
+/// match retrying.await {
+///     Ok(success) => assert_eq!(success.value % 2, 0),
+///     Err(Failure::Timeout { .. }) => println!("Never found an even number :("),
+///     Err(failure) => println!("Stopped by {:?}", failure.reason()),
+/// }
+/// # }
+/// ```
+pub trait RestartableExt<Fut: Future>: Fn() -> Fut + Sized {
+    /// Keeps making and polling futures until one's output passes `test`, or `timeout` expires.
+    /// The same as [`Restartable::new`], with this as the factory.
+    fn restart_until<Test, T, E>(
+        self,
+        timeout: Option<Duration>,
+        test: Test,
+    ) -> Restartable<Fut, Test, Self, T, E>
+    where
+        Test: Fn(Fut::Output) -> Result<T, E>,
+    {
+        Restartable::new(self, timeout, test)
+    }
+}
+
+impl<Fut: Future, Factory: Fn() -> Fut> RestartableExt<Fut> for Factory {}
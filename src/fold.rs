@@ -0,0 +1,66 @@
+use crate::Restartable;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Like [`Restartable::new`], but the test can look at every attempt so far, not just the latest.
+///
+/// `fold` is called with an accumulator (starting at `init`) and each attempt's output. It can
+/// update the accumulator, then return `Some` to resolve the `Restartable`, or `None` to restart.
+/// This supports stop conditions over several attempts, like consensus between them.
+///
+/// The accumulator lives for the whole loop, so it's never reset on restart. If the timeout
+/// expires first, the accumulator is dropped, and the loop fails with `Failure::Err { error: () }`
+/// (or `Failure::Timeout`, if the last attempt never resolved).
+///
+/// ```
+/// use restartables::fold_test;
+/// use std::sync::atomic::{AtomicU8, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Returns 0, 1, 2, 2, 2, ...
+/// let calls = AtomicU8::new(0);
+/// let factory = || {
+///     let n = calls.fetch_add(1, Ordering::SeqCst).min(2);
+///     async move { n }
+/// };
+///
+/// // Succeed once the last three attempts agree.
+/// let retrying = fold_test(
+///     factory,
+///     Some(Duration::from_secs(1)),
+///     Vec::new(),
+///     |recent: &mut Vec<u8>, n: &u8| {
+///         recent.push(*n);
+///         match recent.as_slice() {
+///             [.., a, b, c] if a == b && b == c => Some(*c),
+///             _ => None,
+///         }
+///     },
+/// );
+/// let success = retrying.await.unwrap();
+/// assert_eq!(success.value, 2);
+/// assert_eq!(success.restarts, 4);
+/// # }
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn fold_test<Fut, Factory, T, Acc, Fold>(
+    factory: Factory,
+    timeout: Option<Duration>,
+    init: Acc,
+    fold: Fold,
+) -> Restartable<Fut, impl Fn(Fut::Output) -> Result<T, ()>, Factory, T, ()>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Fold: Fn(&mut Acc, &Fut::Output) -> Option<T>,
+{
+    let acc = Mutex::new(init);
+    let test = move |output| {
+        let mut acc = acc.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        fold(&mut acc, &output).ok_or(())
+    };
+    Restartable::new(factory, timeout, test)
+}
@@ -0,0 +1,61 @@
+//! Helpers for retrying filesystem operations. Requires the `fs` feature, and a tokio runtime.
+
+use crate::{Outcome, Restartable};
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Checks whether `path` exists every `poll_interval`, until it does or the timeout expires.
+///
+/// Resolves to the path's metadata. If the timeout expires first, the error is from the last
+/// check, which is usually `NotFound`.
+///
+/// ```
+/// use restartables::fs::retry_until_path_exists;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let path = std::env::temp_dir().join("restartables-doctest-path-exists");
+/// let _ = std::fs::remove_file(&path);
+///
+/// // Some other task creates the file a little later.
+/// let creating = path.clone();
+/// tokio::spawn(async move {
+///     tokio::time::sleep(Duration::from_millis(50)).await;
+///     tokio::fs::write(creating, b"ready").await.unwrap();
+/// });
+///
+/// let outcome = retry_until_path_exists(
+///     &path,
+///     Some(Duration::from_secs(5)),
+///     Duration::from_millis(10),
+/// )
+/// .await;
+/// let success = outcome.unwrap();
+/// assert_eq!(success.value.len(), 5);
+/// assert!(success.restarts > 0);
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub async fn retry_until_path_exists(
+    path: impl AsRef<Path>,
+    timeout: Option<Duration>,
+    poll_interval: Duration,
+) -> Outcome<Metadata, io::Error> {
+    let path = path.as_ref();
+    let checked = AtomicBool::new(false);
+    let factory = || {
+        // Check straight away the first time, then wait between checks.
+        let wait = checked.swap(true, Ordering::Relaxed);
+        async move {
+            if wait {
+                tokio::time::sleep(poll_interval).await;
+            }
+            tokio::fs::metadata(path).await
+        }
+    };
+    Restartable::new(factory, timeout, |metadata| metadata).await
+}
@@ -0,0 +1,66 @@
+//! Crate-wide default settings, which every Restartable inherits unless it sets its own.
+//!
+//! Set them once, early on, with [`set_global_defaults`]. After that, [`Restartable::new`],
+//! [`Restartable::retry_n`], [`RetryPolicy::apply`], the other constructors, and the helpers built
+//! on them, all start from the global defaults instead of the built-in ones.
+//!
+//! Settings are resolved in this order, from highest precedence to lowest:
+//!
+//! 1. Explicit settings: a `Some` timeout passed to a constructor, a setting on the
+//!    [`RetryPolicy`] being applied, or a builder method like
+//!    [`Restartable::max_pending_polls`] called on the Restartable.
+//! 2. The global defaults.
+//! 3. The built-in defaults: no timeout, no limits, and no yielding.
+//!
+//! Because a `None` timeout falls back to the global default, a Restartable can't opt out of a
+//! global timeout by passing `None`. Pass a longer timeout instead. [`Restartable::retry_n`]
+//! never has a timeout, since it never reads the clock.
+//!
+//! Defaults are read when a Restartable is made, so changing them doesn't affect Restartables
+//! which already exist.
+//!
+//! ```
+//! use restartables::{global, Failure, Restartable, RetryPolicy};
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! global::set_global_defaults(RetryPolicy::builder().max_attempts(3).build());
+//!
+//! let always_fails = |n: u8| Err::<u8, _>(n);
+//! match Restartable::new(|| async { 1 }, None, always_fails).await {
+//!     Err(Failure::Exhausted { restarts, .. }) => assert_eq!(restarts, 2),
+//!     other => panic!("expected exhaustion, got {:?}", other),
+//! }
+//!
+//! // An explicit policy beats the global default.
+//! let policy = RetryPolicy::builder().max_attempts(5).build();
+//! match policy.apply(|| async { 1 }, always_fails).await {
+//!     Err(Failure::Exhausted { restarts, .. }) => assert_eq!(restarts, 4),
+//!     other => panic!("expected exhaustion, got {:?}", other),
+//! }
+//! # }
+//! ```
+//!
+//! [`Restartable::new`]: crate::Restartable::new
+//! [`Restartable::retry_n`]: crate::Restartable::retry_n
+//! [`Restartable::max_pending_polls`]: crate::Restartable::max_pending_polls
+//! [`RetryPolicy::apply`]: crate::RetryPolicy::apply
+
+use crate::RetryPolicy;
+use std::sync::{PoisonError, RwLock};
+
+static DEFAULTS: RwLock<Option<RetryPolicy>> = RwLock::new(None);
+
+/// Sets the defaults which every Restartable made from now on inherits. This replaces any
+/// defaults set before. Set `RetryPolicy::default()` to go back to the built-in defaults.
+pub fn set_global_defaults(policy: RetryPolicy) {
+    *DEFAULTS.write().unwrap_or_else(PoisonError::into_inner) = Some(policy);
+}
+
+/// The current global defaults, if any have been set.
+pub fn global_defaults() -> Option<RetryPolicy> {
+    DEFAULTS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
@@ -0,0 +1,141 @@
+use crate::{Resolve, Restartable};
+use std::future::Future;
+use std::time::Duration;
+
+/// How much each failure has grown the per-attempt timeout, for
+/// [`Restartable::timeout_growth`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TimeoutGrowth {
+    factor: f64,
+    max: Duration,
+    /// What the per-attempt timeout is multiplied by, after the failures so far.
+    scale: f64,
+}
+
+impl TimeoutGrowth {
+    fn new(factor: f64, max: Duration) -> Self {
+        TimeoutGrowth {
+            factor: if factor >= 1.0 { factor } else { 1.0 },
+            max,
+            scale: 1.0,
+        }
+    }
+
+    /// Grows the timeout for the attempts after a failure.
+    pub(crate) fn failed(&mut self) {
+        self.scale *= self.factor;
+    }
+
+    /// The grown version of the per-attempt timeout `base`, up to the cap.
+    pub(crate) fn apply(&self, base: Duration) -> Duration {
+        let secs = base.as_secs_f64() * self.scale;
+        if secs < self.max.as_secs_f64() {
+            Duration::from_secs_f64(secs)
+        } else {
+            self.max
+        }
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Multiply the per-attempt timeout by `factor` after every failed attempt, up to `max`.
+    ///
+    /// This is for backends which are slow because they're struggling: rather than cutting every
+    /// attempt off at the same point, each failure gives the next attempt longer. An attempt fails
+    /// if it fails the test, or is abandoned because it ran past its timeout or
+    /// [`max_pending_polls`](Restartable::max_pending_polls). The growth applies on top of
+    /// [`attempt_timeout_fn`](Restartable::attempt_timeout_fn), and does nothing without it. A
+    /// `factor` below 1 is treated as 1. Unlike a delay between attempts, this doesn't slow down
+    /// retries of attempts which fail quickly.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // The backend takes 30ms to answer, so only attempts given that long can succeed.
+    /// let slow = || async {
+    ///     let start = Instant::now();
+    ///     while start.elapsed() < Duration::from_millis(30) {
+    ///         tokio::task::yield_now().await;
+    ///     }
+    ///     "ok"
+    /// };
+    /// let success = Restartable::new(slow, Some(Duration::from_secs(5)), Ok::<_, ()>)
+    ///     .attempt_timeout_fn(|_| Duration::from_millis(10))
+    ///     .timeout_growth(2.0, Duration::from_millis(100))
+    ///     .await
+    ///     .unwrap();
+    /// // The attempts get 10ms, then 20ms, then 40ms, which is enough.
+    /// assert_eq!(success.restarts, 2);
+    /// # }
+    /// ```
+    pub fn timeout_growth(mut self, factor: f64, max: Duration) -> Self {
+        let growth = TimeoutGrowth::new(factor, max);
+        self.attempt_limit = self.attempt_limit.map(|limit| growth.apply(limit));
+        self.timeout_growth = Some(growth);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_failure_multiplies_the_timeout_up_to_the_cap() {
+        let mut growth = TimeoutGrowth::new(2.0, Duration::from_millis(50));
+        let base = Duration::from_millis(10);
+        let mut sequence = vec![growth.apply(base)];
+        for _ in 0..4 {
+            growth.failed();
+            sequence.push(growth.apply(base));
+        }
+        let millis: Vec<_> = sequence.iter().map(Duration::as_millis).collect();
+        assert_eq!(millis, [10, 20, 40, 50, 50]);
+    }
+
+    #[test]
+    fn factors_below_one_never_shrink_the_timeout() {
+        let mut growth = TimeoutGrowth::new(0.5, Duration::from_secs(1));
+        growth.failed();
+        assert_eq!(
+            growth.apply(Duration::from_millis(10)),
+            Duration::from_millis(10)
+        );
+    }
+
+    #[test]
+    fn failed_attempts_grow_the_next_attempts_timeout() {
+        use std::sync::Arc;
+        use std::task::{Context, Wake, Waker};
+
+        struct NoopWaker;
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+
+        let retrying = Restartable::new(|| std::future::ready(()), None, |()| Err::<(), _>(()))
+            .attempt_timeout_fn(|_| Duration::from_millis(10))
+            .timeout_growth(3.0, Duration::from_millis(100));
+        let mut retrying = Box::pin(retrying);
+        let mut limits = vec![retrying.attempt_limit];
+        for _ in 0..3 {
+            assert!(retrying.as_mut().poll(&mut cx).is_pending());
+            limits.push(retrying.attempt_limit);
+        }
+        let millis: Vec<_> = limits
+            .iter()
+            .map(|limit| limit.unwrap().as_millis())
+            .collect();
+        assert_eq!(millis, [10, 30, 90, 100]);
+    }
+}
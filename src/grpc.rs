@@ -0,0 +1,122 @@
+//! Helpers for retrying [`tonic`] gRPC calls. Requires the `use_tonic` feature.
+//!
+//! A gRPC call fails with a [`tonic::Status`], and its code says whether trying again could help.
+//! [`execute`] retries the calls which failed with a retryable code, according to
+//! [`is_retryable`], and gives up straight away on any other error, e.g. `InvalidArgument`.
+//!
+//! ```no_run
+//! use restartables::grpc;
+//! use std::time::Duration;
+//! use tonic_health::pb::health_client::HealthClient;
+//! use tonic_health::pb::HealthCheckRequest;
+//!
+//! # async fn run() {
+//! let channel = tonic::transport::Channel::from_static("http://[::1]:50051").connect_lazy();
+//! let client = HealthClient::new(channel);
+//! let outcome = grpc::execute(
+//!     || {
+//!         // Generated clients need `&mut self`, but they're cheap to clone.
+//!         let mut client = client.clone();
+//!         async move { client.check(HealthCheckRequest::default()).await }
+//!     },
+//!     Some(Duration::from_secs(5)),
+//! )
+//! .min_retry_interval(Duration::from_millis(100))
+//! .await;
+//! # }
+//! ```
+
+use crate::Restartable;
+use std::future::Future;
+use std::time::Duration;
+use tonic::{Code, Response, Status};
+
+/// The test for [`execute`], which passes a call's result straight through.
+type PassThrough<T> = fn(Result<Response<T>, Status>) -> Result<Response<T>, Status>;
+
+/// Whether a call which failed with `status` might succeed if it's tried again.
+///
+/// These codes are retryable:
+///
+/// - `Unavailable`: the server is down, or couldn't be reached
+/// - `ResourceExhausted`: the server is overloaded, or a quota ran out
+/// - `DeadlineExceeded`: the call took too long
+/// - `Aborted`: the call conflicted with another one, e.g. in a transaction
+///
+/// Every other code, e.g. `InvalidArgument`, `NotFound` or `PermissionDenied`, means the call
+/// would fail the same way again.
+///
+/// ```
+/// use restartables::grpc::is_retryable;
+/// use tonic::Status;
+///
+/// assert!(is_retryable(&Status::unavailable("connection refused")));
+/// assert!(!is_retryable(&Status::invalid_argument("name is empty")));
+/// ```
+pub fn is_retryable(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::ResourceExhausted | Code::DeadlineExceeded | Code::Aborted
+    )
+}
+
+/// Makes a unary call with `factory`, retrying while it fails with a retryable status, until it
+/// succeeds, or the timeout expires.
+///
+/// `factory` is called for every attempt, so it must build a fresh request each time. A status
+/// which [`is_retryable`] restarts the call. Any other status ends the retries straight away,
+/// with [`Failure::NonRetryable`](crate::Failure::NonRetryable). This returns the `Restartable`, so the loop can
+/// be configured further before it's awaited, e.g. with
+/// [`min_retry_interval`](Restartable::min_retry_interval), since a server which is down
+/// usually refuses connections straight away.
+pub fn execute<T, Fut, Factory>(
+    factory: Factory,
+    timeout: Option<Duration>,
+) -> Restartable<Fut, PassThrough<T>, Factory, Response<T>, Status>
+where
+    Fut: Future<Output = Result<Response<T>, Status>>,
+    Factory: Fn() -> Fut,
+{
+    execute_with(factory, timeout, is_retryable)
+}
+
+/// Like [`execute`], but `retryable` decides which statuses to retry.
+///
+/// ```
+/// use restartables::{grpc, Failure};
+/// use std::time::Duration;
+/// use tonic::{Code, Status};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // This service is never going to have it, so don't retry a `NotFound`.
+/// let outcome = grpc::execute_with(
+///     || async { Err::<tonic::Response<()>, _>(Status::not_found("no such user")) },
+///     Some(Duration::from_secs(1)),
+///     |status| status.code() != Code::NotFound,
+/// )
+/// .await;
+/// match outcome {
+///     Err(Failure::NonRetryable { error, restarts, .. }) => {
+///         assert_eq!(error.code(), Code::NotFound);
+///         assert_eq!(restarts, 0);
+///     }
+///     other => panic!("expected to give up, got {:?}", other),
+/// }
+/// # }
+/// ```
+pub fn execute_with<T, Fut, Factory, Retryable>(
+    factory: Factory,
+    timeout: Option<Duration>,
+    retryable: Retryable,
+) -> Restartable<Fut, PassThrough<T>, Factory, Response<T>, Status>
+where
+    Fut: Future<Output = Result<Response<T>, Status>>,
+    Factory: Fn() -> Fut,
+    Retryable: Fn(&Status) -> bool + Send + Sync + 'static,
+{
+    // `Status` is big, but it's what tonic returns, so it isn't worth boxing.
+    #[allow(clippy::result_large_err)]
+    let pass_through: PassThrough<T> = |result| result;
+    Restartable::new(factory, timeout, pass_through).retry_if(retryable)
+}
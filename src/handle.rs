@@ -0,0 +1,221 @@
+use crate::{CompletionReason, Metrics, MetricsSink, Outcome, Resolve, Restartable};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use tokio::task::{JoinError, JoinHandle};
+
+/// A retry loop running in the background, from [`Restartable::spawn_on`].
+///
+/// Await the handle to get the loop's outcome. Like tokio's `JoinHandle`, this is an `Err` if the
+/// task panicked, or the runtime shut down before the loop finished. Dropping the handle doesn't
+/// stop the loop; call [`RetryHandle::cancel`] first for that.
+#[derive(Debug)]
+pub struct RetryHandle<T, E, Raw = ()> {
+    task: JoinHandle<Outcome<T, E, Raw>>,
+    cancel: CancelSignal,
+    progress: Arc<Mutex<RetryMetrics>>,
+}
+
+/// A snapshot of how a spawned retry loop is getting on, from [`RetryHandle::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RetryMetrics {
+    /// How many attempts have been started so far
+    pub attempts: usize,
+    /// How many attempts have failed the test so far
+    pub failures: usize,
+    /// How the loop finished, once it has
+    pub completion: Option<CompletionReason>,
+    /// How long the loop took, once it has finished
+    pub duration: Option<Duration>,
+}
+
+impl<T, E, Raw> RetryHandle<T, E, Raw> {
+    /// Stops the loop. It resolves to [`Failure::Cancelled`](crate::Failure::Cancelled) the next
+    /// time it's polled, which this makes happen straight away. Does nothing if the loop has
+    /// already finished.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// How the loop is getting on so far.
+    pub fn metrics(&self) -> RetryMetrics {
+        *self.progress.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Whether the loop has finished, so awaiting this handle won't wait.
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl<T, E, Raw> Future for RetryHandle<T, E, Raw> {
+    type Output = Result<Outcome<T, E, Raw>, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut self.task).poll(cx)
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future + Send + 'static,
+    Factory: Fn() -> Fut + Send + 'static,
+    Test: Resolve<Fut::Output, Ok = T, Err = E> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+    Raw: Send + 'static,
+    Ctx: Send + 'static,
+{
+    /// Runs this retry loop in the background, on the tokio runtime behind `handle`, and returns
+    /// a [`RetryHandle`] to query, cancel or await it.
+    ///
+    /// This packages up the usual way to run a retry in the background and control it. Cancelling
+    /// the handle works alongside any [`with_cancellation_token`](Restartable::with_cancellation_token),
+    /// and the handle's metrics are kept alongside any [`metrics`](Restartable::metrics) sink.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let never_healthy = || async {
+    ///     tokio::time::sleep(Duration::from_millis(1)).await;
+    ///     Err::<(), _>("unhealthy")
+    /// };
+    /// let retrying = Restartable::from_try_future(never_healthy, None)
+    ///     .spawn_on(&tokio::runtime::Handle::current());
+    /// tokio::time::sleep(Duration::from_millis(20)).await;
+    /// assert!(retrying.metrics().failures > 0);
+    ///
+    /// retrying.cancel();
+    /// assert!(matches!(retrying.await.unwrap(), Err(Failure::Cancelled { .. })));
+    /// # }
+    /// ```
+    pub fn spawn_on(mut self, handle: &tokio::runtime::Handle) -> RetryHandle<T, E, Raw> {
+        let cancel = CancelSignal::default();
+        let cancelled = cancel.cancelled();
+        self.cancelled = Some(match self.cancelled.take() {
+            None => Box::pin(cancelled),
+            Some(mut existing) => {
+                let mut cancelled = Box::pin(cancelled);
+                Box::pin(std::future::poll_fn(move |cx| {
+                    if existing.as_mut().poll(cx).is_ready()
+                        || cancelled.as_mut().poll(cx).is_ready()
+                    {
+                        Poll::Ready(())
+                    } else {
+                        Poll::Pending
+                    }
+                }))
+            }
+        });
+        let progress = Arc::new(Mutex::new(RetryMetrics::default()));
+        self.metrics = Some(Box::new(Tracking {
+            progress: progress.clone(),
+            inner: self.metrics.take(),
+        }));
+        RetryHandle {
+            task: handle.spawn(self),
+            cancel,
+            progress,
+        }
+    }
+}
+
+/// Keeps a [`RetryHandle`]'s metrics up to date, and passes every event on to the Restartable's
+/// own sink, if it has one.
+struct Tracking<T, E, Ctx> {
+    progress: Arc<Mutex<RetryMetrics>>,
+    inner: Option<Metrics<T, E, Ctx>>,
+}
+
+impl<T, E, Ctx> Tracking<T, E, Ctx> {
+    fn lock(&self) -> MutexGuard<'_, RetryMetrics> {
+        self.progress.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T, E, Ctx> MetricsSink<T, E, Ctx> for Tracking<T, E, Ctx> {
+    fn on_attempt_start(&self, attempt: usize, ctx: &Ctx) {
+        self.lock().attempts = attempt.saturating_add(1);
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_start(attempt, ctx);
+        }
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, ctx: &Ctx) {
+        if result.is_err() {
+            let mut progress = self.lock();
+            progress.failures = progress.failures.saturating_add(1);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_end(result, duration, ctx);
+        }
+    }
+
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, ctx: &Ctx) {
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_abandoned(attempt, duration, ctx);
+        }
+    }
+
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        ctx: &Ctx,
+    ) {
+        {
+            let mut progress = self.lock();
+            progress.completion = Some(reason);
+            progress.duration = Some(duration);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_complete(reason, duration, restarts, ctx);
+        }
+    }
+}
+
+/// Cancels a spawned Restartable, without needing a `CancellationToken`.
+#[derive(Debug, Clone, Default)]
+struct CancelSignal {
+    inner: Arc<Mutex<CancelState>>,
+}
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: bool,
+    /// The task waiting for cancellation.
+    waker: Option<Waker>,
+}
+
+impl CancelSignal {
+    fn cancel(&self) {
+        let mut state = self.lock();
+        state.cancelled = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Resolves once this is cancelled.
+    fn cancelled(&self) -> impl Future<Output = ()> + Send + Sync + 'static {
+        let signal = self.clone();
+        std::future::poll_fn(move |cx| {
+            let mut state = signal.lock();
+            if state.cancelled {
+                return Poll::Ready(());
+            }
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CancelState> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,246 @@
+use crate::Backoff;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+
+/// How [`Jittered`] randomises a backoff's delays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Jitter {
+    /// Anywhere from zero up to the whole delay. This spreads retries out the most.
+    Full,
+    /// At least half the delay, plus anywhere up to the other half. This keeps some of the
+    /// backoff's spacing.
+    Equal,
+}
+
+/// A [`Backoff`] which randomises another backoff's delays, so that clients which all failed at
+/// once don't all retry at once, too. Requires the `jitter` feature.
+///
+/// A jittered delay is never longer than the delay it came from, so it never adds to how long a
+/// loop takes. Use [`Jittered::with_seed`] or [`Jittered::with_rng`] for delays which are the
+/// same every run, e.g. in tests.
+///
+/// ```
+/// use restartables::{Backoff, ExponentialBackoff, Jitter, Jittered};
+/// use std::time::Duration;
+///
+/// let exponential = ExponentialBackoff {
+///     base: Duration::from_millis(100),
+///     factor: 2.0,
+///     max: None,
+/// };
+/// let mut backoff = Jittered::with_seed(exponential, Jitter::Equal, 7);
+/// let delay = backoff.delay(1);
+/// assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Jittered<B, R = StdRng> {
+    backoff: B,
+    jitter: Jitter,
+    rng: R,
+}
+
+impl<B> Jittered<B> {
+    /// Randomises `backoff`'s delays with `jitter`, using a randomly seeded RNG.
+    pub fn new(backoff: B, jitter: Jitter) -> Self {
+        Self::with_rng(backoff, jitter, StdRng::from_entropy())
+    }
+
+    /// Randomises `backoff`'s delays with `jitter`, using an RNG seeded with `seed`.
+    pub fn with_seed(backoff: B, jitter: Jitter, seed: u64) -> Self {
+        Self::with_rng(backoff, jitter, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<B, R> Jittered<B, R> {
+    /// Randomises `backoff`'s delays with `jitter`, using `rng`.
+    pub fn with_rng(backoff: B, jitter: Jitter, rng: R) -> Self {
+        Jittered {
+            backoff,
+            jitter,
+            rng,
+        }
+    }
+}
+
+impl<B, R: Rng> Jittered<B, R> {
+    fn jitter(&mut self, delay: Duration) -> Duration {
+        match self.jitter {
+            Jitter::Full => delay.mul_f64(self.rng.gen_range(0.0..=1.0)),
+            Jitter::Equal => delay / 2 + (delay / 2).mul_f64(self.rng.gen_range(0.0..=1.0)),
+        }
+    }
+}
+
+impl<B: Backoff, R: Rng> Backoff for Jittered<B, R> {
+    fn delay(&mut self, restarts: usize) -> Duration {
+        let delay = self.backoff.delay(restarts);
+        self.jitter(delay)
+    }
+
+    /// Stops whenever the inner backoff does.
+    fn next_delay(&mut self, restarts: usize) -> Option<Duration> {
+        let delay = self.backoff.next_delay(restarts)?;
+        Some(self.jitter(delay))
+    }
+
+    fn can_stop(&self) -> bool {
+        self.backoff.can_stop()
+    }
+
+    /// Always `None`, since the delays are random.
+    fn worst_case_total(&self, _max_attempts: Option<usize>) -> Option<Duration>
+    where
+        Self: Clone,
+    {
+        None
+    }
+}
+
+/// AWS's "decorrelated jitter" [`Backoff`]: each delay is random, between `base` and three times
+/// the previous delay, up to `max`. Requires the `jitter` feature.
+///
+/// Unlike [`Jittered`], the delays aren't a fixed schedule with noise on top. They grow roughly
+/// exponentially, but each one depends on the last, so clients which failed together drift apart
+/// quickly. The first failure, i.e. `restarts` 0, starts again from `base`, so this works with
+/// [`Restartable::reset_backoff_after_success`](crate::Restartable::reset_backoff_after_success).
+///
+/// ```
+/// use restartables::{Backoff, Decorrelated};
+/// use std::time::Duration;
+///
+/// let base = Duration::from_millis(10);
+/// let max = Duration::from_secs(1);
+/// let mut backoff = Decorrelated::with_seed(base, max, 7);
+/// for restarts in 0..100 {
+///     let delay = backoff.delay(restarts);
+///     assert!(delay >= base && delay <= max);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decorrelated<R = StdRng> {
+    base: Duration,
+    max: Duration,
+    /// The last delay, which bounds the next one.
+    prev: Duration,
+    rng: R,
+}
+
+impl Decorrelated {
+    /// Delays from `base` up to `max`, using a randomly seeded RNG.
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self::with_rng(base, max, StdRng::from_entropy())
+    }
+
+    /// Delays from `base` up to `max`, using an RNG seeded with `seed`.
+    pub fn with_seed(base: Duration, max: Duration, seed: u64) -> Self {
+        Self::with_rng(base, max, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R> Decorrelated<R> {
+    /// Delays from `base` up to `max`, using `rng`. If `max` is less than `base`, every delay is
+    /// `max`.
+    pub fn with_rng(base: Duration, max: Duration, rng: R) -> Self {
+        Decorrelated {
+            base,
+            max,
+            prev: base,
+            rng,
+        }
+    }
+}
+
+impl<R: Rng> Backoff for Decorrelated<R> {
+    fn delay(&mut self, restarts: usize) -> Duration {
+        if restarts == 0 {
+            self.prev = self.base;
+        }
+        let upper = self.prev.saturating_mul(3);
+        let delay = if upper > self.base {
+            self.rng.gen_range(self.base..=upper)
+        } else {
+            self.base
+        };
+        self.prev = delay.min(self.max);
+        self.prev
+    }
+
+    /// Always `None`, since the delays are random.
+    fn worst_case_total(&self, _max_attempts: Option<usize>) -> Option<Duration>
+    where
+        Self: Clone,
+    {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jittered_delays_stay_in_range() {
+        let base = Duration::from_millis(100);
+        let mut full = Jittered::with_seed(base, Jitter::Full, 1);
+        let mut equal = Jittered::with_seed(base, Jitter::Equal, 1);
+        for restarts in 0..100 {
+            assert!(full.delay(restarts) <= base);
+            let delay = equal.delay(restarts);
+            assert!(delay >= base / 2 && delay <= base);
+        }
+    }
+
+    #[test]
+    fn a_jittered_schedule_still_bounds_the_loop() {
+        use crate::{Delays, Restartable};
+
+        let schedule = vec![Duration::from_millis(10); 3].into_iter();
+        let backoff = Jittered::with_seed(Delays(schedule), Jitter::Full, 1);
+        let retrying =
+            Restartable::new(|| std::future::ready(1), None, Ok::<_, ()>).backoff(backoff);
+        assert_eq!(retrying.validate(), Ok(()));
+    }
+
+    #[test]
+    fn jittered_delays_have_no_worst_case_total() {
+        let backoff = Jittered::with_seed(Duration::from_secs(1), Jitter::Full, 1);
+        assert_eq!(backoff.worst_case_total(Some(3)), None);
+    }
+
+    #[test]
+    fn decorrelated_delays_stay_within_bounds() {
+        let base = Duration::from_millis(10);
+        let max = Duration::from_millis(500);
+        let mut backoff = Decorrelated::with_seed(base, max, 1);
+        let mut prev = base;
+        for restarts in 0..10_000 {
+            let delay = backoff.delay(restarts);
+            assert!(delay >= base && delay <= max);
+            assert!(delay <= prev * 3);
+            prev = delay;
+        }
+        // The delays do grow, rather than staying near the base.
+        assert!((0..100).any(|n| backoff.delay(n + 1) > base * 10));
+    }
+
+    #[test]
+    fn decorrelated_delays_start_again_from_the_base() {
+        let base = Duration::from_millis(10);
+        let mut backoff = Decorrelated::with_seed(base, Duration::from_secs(60), 1);
+        for restarts in 0..20 {
+            backoff.delay(restarts);
+        }
+        assert!(backoff.delay(0) <= base * 3);
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_delays() {
+        let delays = |seed| {
+            let mut backoff = Jittered::with_seed(Duration::from_secs(1), Jitter::Full, seed);
+            (0..10).map(|n| backoff.delay(n)).collect::<Vec<_>>()
+        };
+        assert_eq!(delays(3), delays(3));
+        assert_ne!(delays(3), delays(4));
+    }
+}
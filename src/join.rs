@@ -0,0 +1,226 @@
+use crate::{Failure, Outcome, Restartable, RetryPolicy, Success};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Awaits several retry loops concurrently, and aggregates their metrics.
+///
+/// Resolves once every loop has resolved, whether it succeeded or failed. This is handy for
+/// fan-out health checks, where you want to know how every dependency did.
+///
+/// ```
+/// use restartables::{join_all_retries, Restartable};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let timeout = Some(Duration::from_millis(10));
+/// let retries = vec![2, 3, 4].into_iter().map(|n| {
+///     Restartable::new(move || async move { n }, timeout, |n| {
+///         if n % 2 == 0 { Ok(n) } else { Err("odd") }
+///     })
+/// });
+/// let joined = join_all_retries(retries).await;
+/// assert_eq!(joined.successes().count(), 2);
+/// assert_eq!(joined.failures().count(), 1);
+/// assert!(!joined.all_succeeded());
+/// # }
+/// ```
+pub fn join_all_retries<I>(retries: I) -> JoinAllRetries<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    JoinAllRetries {
+        slots: retries
+            .into_iter()
+            .map(|retry| Slot::Running(Box::pin(retry), None))
+            .collect(),
+        max_concurrent: None,
+    }
+}
+
+/// Future returned by [`join_all_retries`].
+pub struct JoinAllRetries<F: Future> {
+    slots: Vec<Slot<F>>,
+    max_concurrent: Option<usize>,
+}
+
+impl<F: Future> JoinAllRetries<F> {
+    /// Only run `n` loops at once, starting the next loop, in order, whenever a running one
+    /// resolves. Each loop's timeout only starts once it does. `n` is at least 1.
+    pub fn max_concurrent(mut self, n: usize) -> Self {
+        self.max_concurrent = Some(n.max(1));
+        self
+    }
+}
+
+// Running loops are boxed, and finished outcomes are never pinned, so moving this is fine.
+impl<F: Future> Unpin for JoinAllRetries<F> {}
+
+enum Slot<F: Future> {
+    /// The loop, and when it was first polled, if it has been.
+    Running(Pin<Box<F>>, Option<Instant>),
+    Done(F::Output, Duration),
+    Taken,
+}
+
+impl<F, T, E, Raw> Future for JoinAllRetries<F>
+where
+    F: Future<Output = Outcome<T, E, Raw>>,
+{
+    type Output = JoinedOutcome<T, E, Raw>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let now = Instant::now();
+        let mut running = this
+            .slots
+            .iter()
+            .filter(|slot| matches!(slot, Slot::Running(_, Some(_))))
+            .count();
+
+        // Loops start in order, so the ones still waiting to start are all after the others.
+        let mut all_done = true;
+        for slot in this.slots.iter_mut() {
+            if let Slot::Running(retry, started) = slot {
+                if started.is_none() {
+                    if matches!(this.max_concurrent, Some(max) if running >= max) {
+                        all_done = false;
+                        continue;
+                    }
+                    *started = Some(now);
+                    running += 1;
+                }
+                match retry.as_mut().poll(cx) {
+                    Poll::Ready(outcome) => {
+                        let elapsed = started.map_or(Duration::ZERO, |started| started.elapsed());
+                        *slot = Slot::Done(outcome, elapsed);
+                        running -= 1;
+                    }
+                    Poll::Pending => all_done = false,
+                }
+            }
+        }
+        if !all_done {
+            return Poll::Pending;
+        }
+
+        let (outcomes, elapsed) = this
+            .slots
+            .iter_mut()
+            .map(|slot| match std::mem::replace(slot, Slot::Taken) {
+                Slot::Done(outcome, elapsed) => (outcome, elapsed),
+                _ => panic!("JoinAllRetries polled after completion"),
+            })
+            .unzip();
+        Poll::Ready(JoinedOutcome { outcomes, elapsed })
+    }
+}
+
+/// The outcomes of several retry loops, resolved by [`join_all_retries`].
+#[derive(Debug)]
+pub struct JoinedOutcome<T, E, Raw = ()> {
+    outcomes: Vec<Outcome<T, E, Raw>>,
+    elapsed: Vec<Duration>,
+}
+
+impl<T, E, Raw> JoinedOutcome<T, E, Raw> {
+    /// Each loop's outcome, in the same order the loops were given.
+    pub fn outcomes(&self) -> &[Outcome<T, E, Raw>] {
+        &self.outcomes
+    }
+
+    /// Consumes this, returning each loop's outcome, in the same order the loops were given.
+    pub fn into_outcomes(self) -> Vec<Outcome<T, E, Raw>> {
+        self.outcomes
+    }
+
+    /// How long each loop took to resolve, from when it was first polled, in the same order the
+    /// loops were given.
+    pub fn elapsed(&self) -> &[Duration] {
+        &self.elapsed
+    }
+
+    /// How long the slowest loop took to resolve.
+    pub fn max_elapsed(&self) -> Duration {
+        self.elapsed.iter().copied().max().unwrap_or_default()
+    }
+
+    /// Total restarts across every loop, whether it succeeded or failed.
+    pub fn total_restarts(&self) -> usize {
+        self.outcomes
+            .iter()
+            .map(|outcome| match outcome {
+                Ok(success) => success.restarts,
+                Err(failure) => failure.restarts(),
+            })
+            .fold(0, usize::saturating_add)
+    }
+
+    /// The loops which succeeded.
+    pub fn successes(&self) -> impl Iterator<Item = &Success<T, Raw>> {
+        self.outcomes
+            .iter()
+            .filter_map(|outcome| outcome.as_ref().ok())
+    }
+
+    /// The loops which failed.
+    pub fn failures(&self) -> impl Iterator<Item = &Failure<E>> {
+        self.outcomes
+            .iter()
+            .filter_map(|outcome| outcome.as_ref().err())
+    }
+
+    /// True if every loop succeeded.
+    pub fn all_succeeded(&self) -> bool {
+        self.outcomes.iter().all(Result::is_ok)
+    }
+}
+
+/// Retries each of `inputs` independently, following `policy`, with at most `max_concurrent`
+/// retry loops running at once.
+///
+/// `factory` makes each input's futures, and every input's outputs go through the same `test`.
+/// The outcomes are in the same order as the inputs, whichever order they finished in, and the
+/// [`JoinedOutcome`] counts how many succeeded and failed. Each input's timeout from `policy`
+/// starts when its loop does, not when the batch does.
+///
+/// ```
+/// use restartables::{retry_batch, RetryPolicy};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Fetching ids over 100 always fails.
+/// let fetch = |id: &u32| {
+///     let id = *id;
+///     async move { if id > 100 { Err("not found") } else { Ok(id * 2) } }
+/// };
+/// let policy = RetryPolicy::builder().max_attempts(3).build();
+/// let batch = retry_batch(vec![1, 200, 3], fetch, |result| result, policy, 2).await;
+/// assert_eq!(batch.successes().count(), 2);
+/// assert_eq!(batch.failures().count(), 1);
+/// assert!(batch.outcomes()[1].is_err());
+/// # }
+/// ```
+pub async fn retry_batch<Input, Fut, Factory, Test, T, E>(
+    inputs: Vec<Input>,
+    factory: Factory,
+    test: Test,
+    policy: RetryPolicy,
+    max_concurrent: usize,
+) -> JoinedOutcome<T, E>
+where
+    Fut: Future,
+    Factory: Fn(&Input) -> Fut,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+{
+    let factory = &factory;
+    let retries = inputs
+        .iter()
+        .map(|input| -> Restartable<_, _, _, T, E> { policy.apply(move || factory(input), &test) });
+    join_all_retries(retries)
+        .max_concurrent(max_concurrent)
+        .await
+}
@@ -60,22 +60,107 @@
 //!             success.duration.as_micros(),
 //!             success.restarts
 //!         ),
-//!         Err(Failure::Timeout) => println!("Never found an even number :("),
-//!         Err(Failure::Err { error, restarts }) => {
-//!             println!("Error {} after {} restarts", error, restarts)
-//!         }
+//!         Err(Failure::Timeout { .. }) => println!("Never found an even number :("),
+//!         Err(Failure::Err {
+//!             error,
+//!             restarts,
+//!             elapsed,
+//!             ..
+//!         }) => println!(
+//!             "Error {} after {} restarts and {}us",
+//!             error,
+//!             restarts,
+//!             elapsed.as_micros()
+//!         ),
+//!         Err(failure) => println!("Stopped by {:?}", failure.reason()),
 //!     };
 //! }
 //! ```
 
+mod async_test;
+mod backoff;
+mod bench;
+mod builder;
+mod clock;
+mod collect;
+mod deadline;
+mod delay;
+pub mod expect;
+mod ext;
+mod fold;
+#[cfg(feature = "fs")]
+pub mod fs;
+#[cfg(feature = "global_defaults")]
+pub mod global;
+mod growth;
+#[cfg(feature = "use_tonic")]
+pub mod grpc;
+#[cfg(feature = "use_tokio")]
+mod handle;
+#[cfg(feature = "jitter")]
+mod jitter;
+mod join;
+mod macros;
+mod metrics;
+mod numbered;
+mod once;
 mod outcome;
+mod pause;
+mod policy;
+mod rate;
+mod recent;
+mod report;
+#[cfg(feature = "use_reqwest")]
+pub mod reqw;
+mod resolution;
+mod spawn;
+mod state;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod then;
+#[cfg(feature = "use_tower")]
+pub mod tower;
+mod try_factory;
+mod validate;
+mod yielding;
 
-pub use outcome::{Failure, Success};
+pub use async_test::{async_test, AsyncTested};
+pub use backoff::{Backoff, Delays, ExponentialBackoff, FixedInterval, NoBackoff};
+pub use bench::{benchmark, BenchSuccess};
+pub use builder::RestartableBuilder;
+pub use clock::{Clock, SystemClock};
+pub use collect::collect_until_timeout;
+pub use deadline::Deadline;
+pub use ext::RestartableExt;
+pub use fold::fold_test;
+#[cfg(feature = "use_tokio")]
+pub use handle::{RetryHandle, RetryMetrics};
+#[cfg(feature = "jitter")]
+pub use jitter::{Decorrelated, Jitter, Jittered};
+pub use join::{join_all_retries, retry_batch, JoinAllRetries, JoinedOutcome};
+pub use metrics::{AttemptLog, AttemptRecord, MetricsSink, NoopSink, StdoutSink};
+pub use numbered::numbered_test;
+pub use once::SingleShot;
+pub use outcome::{
+    CompletionReason, Failure, Outcome, OutcomeReason, StopReason, Stopped, Success, TimeoutScope,
+    UnmatchedStop,
+};
+pub use pause::Pauser;
 use pin_project::pin_project;
+pub use policy::{RetryPolicy, RetryPolicyBuilder};
+pub use report::RetryReport;
+pub use resolution::{Inverted, Numbered, Resolution, Resolve, Resolving};
+pub use spawn::Spawner;
+#[cfg(feature = "use_tokio")]
+pub use spawn::TokioSpawner;
+pub use state::RetryState;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
+pub use try_factory::{try_factory, TryAttempt};
+pub use validate::ConfigError;
 
 /// Wraps an inner future, restarting it until it resolves a value that passes a test, or times out.
 ///
@@ -90,23 +175,252 @@ use std::time::{Duration, Instant};
 ///
 /// Because this fail-restart loop could go on forever, you should supply a timeout. If a `None`
 /// timeout is used, then awaiting the `Restartable` might never finish (because of this fail-restart
-/// loop).
-#[pin_project]
-pub struct Restartable<Fut, Test, Factory, T, E>
+/// loop), and a warning is printed unless [`Restartable::allow_unbounded`] is used. A zero
+/// timeout allows exactly one poll of the first attempt, and no restarts: if that
+/// poll doesn't produce a value which passes the test, the `Restartable` gives up straight away.
+///
+/// The inner future doesn't need to be `Unpin`, so factories can return `async` blocks, even ones
+/// which hold borrows across `.await` points. On restart, the old future is dropped in place
+/// before the new one is written into the same pinned memory, which is what `Pin` requires.
+///
+/// ```
+/// use restartables::Restartable;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let attempts = AtomicUsize::new(0);
+/// let factory = || async {
+///     let data = [attempts.fetch_add(1, Ordering::SeqCst); 3];
+///     // Borrowing `data` across an await makes this future self-referential.
+///     let first = &data[0];
+///     tokio::task::yield_now().await;
+///     *first
+/// };
+/// let retrying = Restartable::new(factory, Some(Duration::from_secs(1)), |attempt| {
+///     if attempt == 2 { Ok(attempt) } else { Err(attempt) }
+/// });
+/// let success = retrying.await.unwrap();
+/// assert_eq!(success.value, 2);
+/// assert_eq!(success.restarts, 2);
+/// # }
+/// ```
+///
+/// # Waking
+///
+/// A freshly restarted future hasn't been polled yet, so nothing will wake the task to poll it.
+/// Instead, the `Restartable` wakes its own task (with `cx.waker().wake_by_ref()`) before
+/// returning `Poll::Pending`. `Future` requires executors to honour wakes which happen during a
+/// poll, so every restart is followed by another poll, on any executor:
+///
+/// ```
+/// use restartables::Restartable;
+/// use std::future::Future;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use std::task::{Context, Poll, Wake, Waker};
+///
+/// // A waker which only counts how many times it was woken.
+/// struct CountingWaker(AtomicUsize);
+/// impl Wake for CountingWaker {
+///     fn wake(self: Arc<Self>) {
+///         self.wake_by_ref();
+///     }
+///     fn wake_by_ref(self: &Arc<Self>) {
+///         self.0.fetch_add(1, Ordering::SeqCst);
+///     }
+/// }
+///
+/// let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+/// let waker = Waker::from(counter.clone());
+/// let mut cx = Context::from_waker(&waker);
+///
+/// // Fails the first attempt, passes the second.
+/// let attempts = AtomicUsize::new(0);
+/// let factory = || std::future::ready(attempts.fetch_add(1, Ordering::SeqCst));
+/// let retrying = Restartable::new(factory, None, |n| if n > 0 { Ok(n) } else { Err(n) });
+/// let mut retrying = Box::pin(retrying);
+///
+/// // The first attempt fails, so the Restartable restarts and asks to be polled again.
+/// assert!(retrying.as_mut().poll(&mut cx).is_pending());
+/// assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+///
+/// // Polling again gets the second attempt's value.
+/// match retrying.as_mut().poll(&mut cx) {
+///     Poll::Ready(Ok(success)) => assert_eq!(success.restarts, 1),
+///     other => panic!("expected success, got {:?}", other),
+/// }
+/// ```
+///
+/// So by default, an attempt which fails straight away is restarted as fast as the executor can
+/// poll, and a loop of them uses a whole CPU core until it ends. To bound how often it retries,
+/// set a [`min_retry_interval`](Restartable::min_retry_interval) or a
+/// [`backoff`](Restartable::backoff), which sleep between attempts without waking the task. To
+/// just let other tasks run between attempts, use
+/// [`yield_between_attempts`](Restartable::yield_between_attempts).
+///
+/// # Sending between threads
+///
+/// A `Restartable` is `Send` whenever the future, factory, test, success value `T`, raw output
+/// and context are all `Send`, and likewise for `Sync`. The error type `E` doesn't matter, because
+/// errors are never held between polls. Every callback given to a builder method, like
+/// [`Restartable::inspect`] or [`Restartable::gate_next_attempt`], is already required to be
+/// `Send + Sync`, so none of them stop a `Restartable` from being spawned.
+///
+/// So if spawning a `Restartable` on a multithreaded runtime fails because it isn't `Send`, look
+/// at what the factory and test capture, and at what the futures from the factory hold across
+/// `.await`s. A common culprit is an `Rc`, or a `RefCell` borrow, held across an `.await`.
+#[pin_project(project = RestartableProj)]
+pub struct Restartable<Fut, Test, Factory, T, E, Raw = (), Ctx = ()>
 where
     Fut: Future,
     Factory: Fn() -> Fut,
-    Test: Fn(Fut::Output) -> Result<T, E>,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
 {
     #[pin]
     future: Fut,
     start: Option<Instant>,
     factory: Factory,
     timeout: Option<Duration>,
+    /// Shared with other loops, unlike `timeout`, which starts when this loop is first polled.
+    deadline: Option<Deadline>,
     test: Test,
     restarts: usize,
+    cancelled: Option<Cancelled>,
+    background: Option<Background>,
+    inspect: Option<Inspect<T, E, Ctx>>,
+    max_pending_polls: Option<usize>,
+    pending_polls: usize,
+    attempt_timeout: Option<AttemptTimeout>,
+    /// The current attempt's timeout, from `attempt_timeout`.
+    attempt_limit: Option<Duration>,
+    timeout_growth: Option<growth::TimeoutGrowth>,
+    attempt_start: Option<Instant>,
+    /// How long every attempt which resolved took, for `Success::attempt_durations`.
+    attempt_durations: Vec<Duration>,
+    /// The most recent value from `Resolution::AcceptButContinue`.
+    provisional: Option<Success<T, Raw>>,
+    /// Whether to read the clock at all. If not, every duration is zero.
+    timed: bool,
+    clock: Arc<dyn Clock + Send + Sync>,
+    max_attempts: Option<usize>,
+    /// Whether `max_attempts` came from `max_restarts`, so running out is `Failure::MaxRetries`.
+    restarts_capped: bool,
+    /// Copies the inner future's output for `Success::raw_output`, if that's enabled.
+    keep_raw: fn(&Fut::Output) -> Option<Raw>,
+    /// Whether an attempt's output means the factory couldn't build it, for [`try_factory`].
+    factory_failed: fn(&Fut::Output) -> bool,
+    until: Option<Until>,
+    /// Set after a restart, from `until`. Polled before the new attempt.
+    stopping: Option<StopCheck>,
+    min_retry_interval: Option<Duration>,
+    backoff: Option<Box<dyn Backoff + Send + Sync>>,
+    /// Overrides `backoff` after a test failure.
+    backoff_for: Option<BackoffFor<E>>,
+    /// How many attempts have failed and backed off, which is what `backoff` is given.
+    backoff_position: usize,
+    /// How many passing attempts in a row reset `backoff_position`, from
+    /// `reset_backoff_after_success`.
+    reset_backoff_after: Option<usize>,
+    /// How many attempts in a row have passed the test but restarted anyway.
+    consecutive_passes: usize,
+    /// The most of the remaining time budget one wait between attempts may use.
+    max_backoff_fraction: Option<f64>,
+    /// Set after a restart, from `min_retry_interval` and `backoff`. Polled before the new attempt.
+    delaying: Option<delay::Delay>,
+    yield_between_attempts: bool,
+    /// Set after a restart, if `yield_between_attempts` is on. Polled before the new attempt.
+    yielding: Option<yielding::Yield>,
+    gate: Option<Gate>,
+    /// Set after a restart, from `gate`. Polled before the new attempt.
+    gating: Option<GateFuture>,
+    probe: Option<Probe<E>>,
+    /// Set when an attempt starts, from `probe`. Polled before the attempt's own future.
+    probing: Option<ConfirmFuture<E>>,
+    confirm: Option<Confirm<E>>,
+    /// A value which passed the test, and the confirmation it's waiting on.
+    confirming: Option<(ConfirmFuture<E>, Success<T, Raw>)>,
+    metrics: Option<Metrics<T, E, Ctx>>,
+    /// Whether `metrics` has been told the current attempt started.
+    attempt_reported: bool,
+    /// Passed to every hook, from `with_context`.
+    ctx: Ctx,
+    repeated: Option<Repeated<E>>,
+    retryable: Option<Retryable<E>>,
+    success_rate: Option<rate::SuccessRate<T, Raw>>,
+    recent_errors: Option<Box<dyn recent::ErrorLog<E> + Send + Sync>>,
+    /// From `with_pauser`, and how long it had been paused for when `start` was set.
+    pauser: Option<(Pauser, Duration)>,
+    /// From `allow_unbounded`, to silence the warning about retrying forever.
+    allow_unbounded: bool,
+    /// How long the loop had already run for, if it was made with `resume`.
+    resumed_elapsed: Duration,
 }
 
+/// Spawns the background retries for [`Restartable::return_first_and_continue`], given the
+/// settings they carry on with.
+type Background = Box<dyn FnOnce(spawn::Carried) + Send + Sync>;
+/// Resolves once the Restartable is cancelled, for `Restartable::with_cancellation_token` and
+/// `Restartable::spawn_on`.
+type Cancelled = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+/// Callback for [`Restartable::inspect`] and [`Restartable::inspect_with_context`].
+type Inspect<T, E, Ctx> = Box<dyn Fn(&Result<T, E>, &Ctx) + Send + Sync>;
+/// Computes each attempt's timeout for [`Restartable::attempt_timeout_fn`].
+type AttemptTimeout = Box<dyn Fn(usize) -> Duration + Send + Sync>;
+/// Asks whether to stop retrying, for [`Restartable::until`].
+type Until = Box<dyn Fn() -> StopCheck + Send + Sync>;
+type StopCheck = Pin<Box<dyn Future<Output = bool> + Send + Sync>>;
+/// Opens a gate for [`Restartable::gate_next_attempt`].
+type Gate = Box<dyn Fn() -> GateFuture + Send + Sync>;
+type GateFuture = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+/// Starts a probe for [`Restartable::probe_with`].
+type Probe<E> = Box<dyn Fn() -> ConfirmFuture<E> + Send + Sync>;
+/// Starts a confirmation for [`Restartable::confirm_with`].
+type Confirm<E> = Box<dyn Fn() -> ConfirmFuture<E> + Send + Sync>;
+/// Decides whether an error is worth retrying, for [`Restartable::retry_if`].
+type Retryable<E> = Box<dyn Fn(&E) -> bool + Send + Sync>;
+/// Picks the delay after an error, given the backoff position, for
+/// [`Restartable::backoff_for`].
+type BackoffFor<E> = Box<dyn Fn(&E, usize) -> Option<Duration> + Send + Sync>;
+
+/// Tracks the run of identical errors for [`Restartable::give_up_on_repeated_error`].
+type Repeated<E> = Box<dyn RepeatedErrors<E> + Send + Sync>;
+
+/// A run of identical errors, for [`Restartable::give_up_on_repeated_error`].
+trait RepeatedErrors<E> {
+    /// Counts `error` against the current run, and says whether it's been repeated too many
+    /// times in a row.
+    fn is_repeat(&mut self, error: &E) -> bool;
+    /// Keeps `error` to compare the next failure against.
+    fn remember(&mut self, error: E);
+}
+
+struct Run<E> {
+    n: usize,
+    count: usize,
+    last: Option<E>,
+}
+
+impl<E: PartialEq> RepeatedErrors<E> for Run<E> {
+    fn is_repeat(&mut self, error: &E) -> bool {
+        self.count = if self.last.as_ref() == Some(error) {
+            self.count + 1
+        } else {
+            1
+        };
+        self.count >= self.n
+    }
+
+    fn remember(&mut self, error: E) {
+        self.last = Some(error);
+    }
+}
+
+/// Sink for [`Restartable::metrics`].
+type Metrics<T, E, Ctx> = Box<dyn MetricsSink<T, E, Ctx> + Send + Sync>;
+type ConfirmFuture<E> = Pin<Box<dyn Future<Output = Result<(), E>> + Send + Sync>>;
+
 impl<Fut, Test, Factory, T, E> Restartable<Fut, Test, Factory, T, E>
 where
     Fut: Future,
@@ -114,64 +428,2445 @@ where
     Test: Fn(Fut::Output) -> Result<T, E>,
 {
     pub fn new(factory: Factory, timeout: Option<Duration>, test: Test) -> Self {
+        Self::from_parts(factory, timeout, test).with_global_defaults()
+    }
+
+    /// Makes at most `n` attempts, with no timeout, resolving to [`Failure::Exhausted`] if they
+    /// all fail the test.
+    ///
+    /// This never reads the clock, so there's no timing overhead, but the `Success::duration` it
+    /// reports is always zero. The first attempt is always made, so `n == 0` behaves like `n == 1`.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let retrying = Restartable::retry_n(|| async { 3 }, |n| if n % 2 == 0 { Ok(n) } else { Err(n) }, 5);
+    /// match retrying.await {
+    ///     Err(Failure::Exhausted { error, restarts }) => {
+    ///         assert_eq!(error, 3);
+    ///         assert_eq!(restarts, 4);
+    ///     }
+    ///     other => panic!("expected exhaustion, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn retry_n(factory: Factory, test: Test, n: usize) -> Self {
+        let mut restartable = Self::from_parts(factory, None, test).with_global_defaults();
+        restartable.timeout = None;
+        // A default per-attempt timeout still needs the attempts timed.
+        restartable.timed = restartable.attempt_timeout.is_some();
+        restartable.max_attempts = Some(n);
+        restartable
+    }
+
+    /// Like [`Restartable::new`], but restarts at most `max_restarts` times. See
+    /// [`Restartable::max_restarts`].
+    pub fn with_max_restarts(
+        factory: Factory,
+        timeout: Option<Duration>,
+        test: Test,
+        max_restarts: usize,
+    ) -> Self {
+        Self::new(factory, timeout, test).max_restarts(max_restarts)
+    }
+
+    /// Like [`Restartable::new`], but stops at an absolute `deadline` instead of after a relative
+    /// timeout, e.g. for a deadline computed upstream in a request pipeline.
+    ///
+    /// Each check compares the current time against `deadline` directly, so there's no converting
+    /// it to a `Duration` and back, which would lose the time spent in between. Passing the
+    /// deadline resolves like the timeout expiring. See [`Restartable::deadline`].
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// let retrying = Restartable::with_deadline(|| async { 1 }, deadline, |n: u8| Err::<(), _>(n));
+    /// match retrying.await {
+    ///     Err(Failure::Err { error, .. }) => {
+    ///         assert_eq!(error, 1);
+    ///         assert!(Instant::now() > deadline);
+    ///     }
+    ///     other => panic!("expected the deadline to pass, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn with_deadline(factory: Factory, deadline: Instant, test: Test) -> Self {
+        let mut restartable = Self::from_parts(factory, None, test);
+        restartable.deadline = Some(Deadline::at(deadline));
+        restartable.with_global_defaults()
+    }
+}
+
+impl<Fut, Factory, T, E> Restartable<Fut, fn(Result<T, E>) -> Result<T, E>, Factory, T, E>
+where
+    Fut: Future<Output = Result<T, E>>,
+    Factory: Fn() -> Fut,
+{
+    /// Like [`Restartable::new`], for futures which already return a `Result`. `Ok` passes the
+    /// test, and `Err` fails it.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let attempts = AtomicUsize::new(0);
+    /// let flaky = || async {
+    ///     match attempts.fetch_add(1, Ordering::SeqCst) {
+    ///         0 | 1 => Err("not yet"),
+    ///         n => Ok(n),
+    ///     }
+    /// };
+    /// let success = Restartable::from_try_future(flaky, Some(Duration::from_secs(1)))
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(success.value, 2);
+    /// # }
+    /// ```
+    pub fn from_try_future(factory: Factory, timeout: Option<Duration>) -> Self {
+        Self::from_parts(factory, timeout, std::convert::identity).with_global_defaults()
+    }
+}
+
+impl<Fut, F, Factory, T, E> Restartable<Fut, Resolving<F>, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    F: Fn(Fut::Output) -> Resolution<T, E>,
+{
+    /// Like [`Restartable::new`], but the test returns a three-way [`Resolution`].
+    ///
+    /// This lets the test mark a value as good enough to return, while still retrying in case a
+    /// later attempt is better. Returning `Accept` or `Reject` behaves like returning `Ok` or `Err`
+    /// from a normal test.
+    ///
+    /// ```
+    /// use restartables::{Resolution, Restartable};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Any multiple of 10 is fine, but keep trying for a multiple of 1000 until the timeout.
+    /// let retrying = Restartable::with_resolution(
+    ///     || async { rand::random::<u16>() },
+    ///     Some(Duration::from_millis(10)),
+    ///     |n| match n {
+    ///         n if n % 1000 == 0 => Resolution::Accept(n),
+    ///         n if n % 10 == 0 => Resolution::AcceptButContinue(n),
+    ///         n => Resolution::Reject(n),
+    ///     },
+    /// );
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.value % 10, 0);
+    /// # }
+    /// ```
+    pub fn with_resolution(factory: Factory, timeout: Option<Duration>, resolve: F) -> Self {
+        Self::from_parts(factory, timeout, Resolving(resolve)).with_global_defaults()
+    }
+}
+
+impl<Fut, F, Factory, T, E> Restartable<Fut, Inverted<F>, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    F: Fn(Fut::Output) -> Result<E, T>,
+{
+    /// Like [`Restartable::new`], but with the test's semantics inverted: `Ok` restarts the inner
+    /// future, and `Err` resolves the Restartable.
+    ///
+    /// This is for polling until something is done, like a job whose status is `Running` until it
+    /// finishes. The test's `Err` value is the `Success` value, and if the timeout expires,
+    /// `Failure::Err` holds the last `Ok` value.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Status {
+    ///     Running(usize),
+    ///     Done,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let polls = AtomicUsize::new(0);
+    /// let job_status = || async {
+    ///     match polls.fetch_add(1, Ordering::SeqCst) {
+    ///         n if n < 3 => Status::Running(n),
+    ///         _ => Status::Done,
+    ///     }
+    /// };
+    /// let still_running = |status| match status {
+    ///     Status::Running(n) => Ok(n),
+    ///     Status::Done => Err(Status::Done),
+    /// };
+    /// let retrying = Restartable::until_err(job_status, Some(Duration::from_secs(1)), still_running);
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.value, Status::Done);
+    /// assert_eq!(success.restarts, 3);
+    /// # }
+    /// ```
+    pub fn until_err(factory: Factory, timeout: Option<Duration>, test: F) -> Self {
+        Self::from_parts(factory, timeout, Inverted(test)).with_global_defaults()
+    }
+}
+
+impl<Fut, Test, Factory, T, E> Restartable<Fut, Test, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Fills in any settings which haven't been set, from the global defaults, if there are any.
+    #[cfg(feature = "global_defaults")]
+    fn with_global_defaults(mut self) -> Self {
+        if let Some(defaults) = global::global_defaults() {
+            defaults.fill_in(&mut self);
+        }
+        self
+    }
+
+    #[cfg(not(feature = "global_defaults"))]
+    fn with_global_defaults(self) -> Self {
+        self
+    }
+
+    pub(crate) fn from_parts(factory: Factory, timeout: Option<Duration>, test: Test) -> Self {
         Restartable {
             future: factory(),
             factory,
             timeout,
+            deadline: None,
             test,
             start: None,
             restarts: 0,
+            cancelled: None,
+            background: None,
+            inspect: None,
+            max_pending_polls: None,
+            pending_polls: 0,
+            attempt_timeout: None,
+            attempt_limit: None,
+            timeout_growth: None,
+            attempt_start: None,
+            attempt_durations: Vec::new(),
+            provisional: None,
+            timed: true,
+            clock: Arc::new(SystemClock),
+            max_attempts: None,
+            restarts_capped: false,
+            keep_raw: |_| None,
+            factory_failed: |_| false,
+            until: None,
+            stopping: None,
+            min_retry_interval: None,
+            backoff: None,
+            backoff_for: None,
+            backoff_position: 0,
+            reset_backoff_after: None,
+            consecutive_passes: 0,
+            max_backoff_fraction: None,
+            delaying: None,
+            yield_between_attempts: false,
+            yielding: None,
+            gate: None,
+            gating: None,
+            probe: None,
+            probing: None,
+            confirm: None,
+            confirming: None,
+            metrics: None,
+            attempt_reported: false,
+            ctx: (),
+            repeated: None,
+            retryable: None,
+            success_rate: None,
+            recent_errors: None,
+            pauser: None,
+            allow_unbounded: false,
+            resumed_elapsed: Duration::ZERO,
         }
     }
 }
 
-impl<Fut, Test, Factory, T, E> Future for Restartable<Fut, Test, Factory, T, E>
+impl<Fut, Test, Factory, T, E, Ctx> Restartable<Fut, Test, Factory, T, E, (), Ctx>
 where
     Fut: Future,
     Factory: Fn() -> Fut,
-    Test: Fn(Fut::Output) -> Result<T, E>,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Keep a copy of the inner future's output from the winning attempt, as
+    /// [`Success::raw_output`].
+    ///
+    /// This is useful when the test maps the output into a narrower value, but you still want the
+    /// original, e.g. for logging. The output is cloned before it's passed to the test.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let retrying = Restartable::new(
+    ///     || async { "42" },
+    ///     Some(Duration::from_secs(1)),
+    ///     |s: &str| s.parse::<u8>(),
+    /// )
+    /// .retain_raw_output();
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.value, 42);
+    /// assert_eq!(success.raw_output, Some("42"));
+    /// # }
+    /// ```
+    pub fn retain_raw_output(self) -> Restartable<Fut, Test, Factory, T, E, Fut::Output, Ctx>
+    where
+        Fut::Output: Clone,
+    {
+        Restartable {
+            future: self.future,
+            start: self.start,
+            factory: self.factory,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            test: self.test,
+            restarts: self.restarts,
+            cancelled: self.cancelled,
+            background: self.background,
+            inspect: self.inspect,
+            max_pending_polls: self.max_pending_polls,
+            pending_polls: self.pending_polls,
+            attempt_timeout: self.attempt_timeout,
+            attempt_limit: self.attempt_limit,
+            timeout_growth: self.timeout_growth,
+            attempt_start: self.attempt_start,
+            attempt_durations: self.attempt_durations,
+            provisional: None,
+            timed: self.timed,
+            clock: self.clock,
+            max_attempts: self.max_attempts,
+            restarts_capped: self.restarts_capped,
+            keep_raw: |output| Some(output.clone()),
+            factory_failed: self.factory_failed,
+            until: self.until,
+            stopping: self.stopping,
+            min_retry_interval: self.min_retry_interval,
+            backoff: self.backoff,
+            backoff_for: self.backoff_for,
+            backoff_position: self.backoff_position,
+            reset_backoff_after: self.reset_backoff_after,
+            consecutive_passes: self.consecutive_passes,
+            max_backoff_fraction: self.max_backoff_fraction,
+            delaying: self.delaying,
+            yield_between_attempts: self.yield_between_attempts,
+            yielding: self.yielding,
+            gate: self.gate,
+            gating: self.gating,
+            probe: self.probe,
+            probing: self.probing,
+            confirm: self.confirm,
+            confirming: None,
+            metrics: self.metrics,
+            attempt_reported: self.attempt_reported,
+            ctx: self.ctx,
+            repeated: self.repeated,
+            retryable: self.retryable,
+            success_rate: self.success_rate.map(rate::SuccessRate::forget_raw),
+            recent_errors: self.recent_errors,
+            pauser: self.pauser,
+            allow_unbounded: self.allow_unbounded,
+            resumed_elapsed: self.resumed_elapsed,
+        }
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw> Restartable<Fut, Test, Factory, T, E, Raw>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+    T: 'static,
+    E: 'static,
+{
+    /// Store `ctx` for the whole retry loop, and pass a reference to it to every hook.
+    ///
+    /// This is for correlating hook calls, e.g. with a request ID or a tracing span, without
+    /// capturing it in every closure. [`Restartable::inspect_with_context`] and any
+    /// [`MetricsSink`] receive it. Hooks which were set before this get `&()`, as before.
+    ///
+    /// ```
+    /// use restartables::{MetricsSink, Restartable};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::Duration;
+    ///
+    /// struct RequestId(u32);
+    ///
+    /// #[derive(Default)]
+    /// struct Log(Mutex<Vec<String>>);
+    ///
+    /// impl<T, E> MetricsSink<T, E, RequestId> for Log {
+    ///     fn on_attempt_start(&self, attempt: usize, ctx: &RequestId) {
+    ///         self.0.lock().unwrap().push(format!("request {} attempt {}", ctx.0, attempt));
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let log = Arc::new(Log::default());
+    /// let retrying = Restartable::retry_n(|| async { 1 }, |n| Err::<(), _>(n), 2)
+    ///     .with_context(RequestId(7))
+    ///     .metrics(log.clone());
+    /// assert_eq!(retrying.context().0, 7);
+    /// assert!(retrying.await.is_err());
+    /// assert_eq!(
+    ///     *log.0.lock().unwrap(),
+    ///     vec!["request 7 attempt 0", "request 7 attempt 1"]
+    /// );
+    /// # }
+    /// ```
+    pub fn with_context<Ctx>(self, ctx: Ctx) -> Restartable<Fut, Test, Factory, T, E, Raw, Ctx> {
+        Restartable {
+            future: self.future,
+            start: self.start,
+            factory: self.factory,
+            timeout: self.timeout,
+            deadline: self.deadline,
+            test: self.test,
+            restarts: self.restarts,
+            cancelled: self.cancelled,
+            background: self.background,
+            inspect: self.inspect.map(|inspect| -> Inspect<T, E, Ctx> {
+                Box::new(move |result, _| inspect(result, &()))
+            }),
+            max_pending_polls: self.max_pending_polls,
+            pending_polls: self.pending_polls,
+            attempt_timeout: self.attempt_timeout,
+            attempt_limit: self.attempt_limit,
+            timeout_growth: self.timeout_growth,
+            attempt_start: self.attempt_start,
+            attempt_durations: self.attempt_durations,
+            provisional: self.provisional,
+            timed: self.timed,
+            clock: self.clock,
+            max_attempts: self.max_attempts,
+            restarts_capped: self.restarts_capped,
+            keep_raw: self.keep_raw,
+            factory_failed: self.factory_failed,
+            until: self.until,
+            stopping: self.stopping,
+            min_retry_interval: self.min_retry_interval,
+            backoff: self.backoff,
+            backoff_for: self.backoff_for,
+            backoff_position: self.backoff_position,
+            reset_backoff_after: self.reset_backoff_after,
+            consecutive_passes: self.consecutive_passes,
+            max_backoff_fraction: self.max_backoff_fraction,
+            delaying: self.delaying,
+            yield_between_attempts: self.yield_between_attempts,
+            yielding: self.yielding,
+            gate: self.gate,
+            gating: self.gating,
+            probe: self.probe,
+            probing: self.probing,
+            confirm: self.confirm,
+            confirming: self.confirming,
+            metrics: self
+                .metrics
+                .map(|metrics| -> Metrics<T, E, Ctx> { Box::new(metrics::IgnoreContext(metrics)) }),
+            attempt_reported: self.attempt_reported,
+            ctx,
+            repeated: self.repeated,
+            retryable: self.retryable,
+            success_rate: self.success_rate,
+            recent_errors: self.recent_errors,
+            pauser: self.pauser,
+            allow_unbounded: self.allow_unbounded,
+            resumed_elapsed: self.resumed_elapsed,
+        }
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
 {
-    type Output = Result<Success<T>, Failure<E>>;
+    /// The context from [`Restartable::with_context`].
+    pub fn context(&self) -> &Ctx {
+        &self.ctx
+    }
+
+    /// How many attempts have been started so far, including the current one. This is one more
+    /// than the number of restarts.
+    pub fn attempts(&self) -> usize {
+        self.restarts.saturating_add(1)
+    }
+
+    /// The current attempt's future, for instrumentation the built-in hooks don't cover.
+    ///
+    /// Every restart replaces this future with a fresh one from the factory, so what this returns
+    /// is only the current attempt, and only until the Restartable is polled again. Before the
+    /// first poll, it's the first attempt, which the constructor already built.
+    pub fn inner(&self) -> &Fut {
+        &self.future
+    }
+
+    /// The current attempt's future, pinned, so it can be interacted with between polls.
+    ///
+    /// This has the same caveats as [`Restartable::inner`], plus some sharp edges:
+    ///
+    /// - Polling the inner future yourself takes its output away from the Restartable. If it
+    ///   resolves, the Restartable will poll a finished future next time, which many futures
+    ///   panic on. Polling it with a different waker can also leave the Restartable unwoken.
+    /// - Anything done to the future counts towards the attempt's duration and timeouts, as
+    ///   usual, and doesn't reset them.
+    /// - The future can't be replaced, only interacted with through its own pinned API.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::time::Duration;
+    /// use tokio::time::{sleep, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let factory = || sleep(Duration::from_secs(60));
+    /// let retrying = Restartable::new(factory, None, Ok::<(), ()>);
+    /// tokio::pin!(retrying);
+    /// // Hurry the first attempt along, by moving its sleep's deadline to now.
+    /// retrying.as_mut().inner_mut().reset(Instant::now());
+    /// let success = retrying.await.unwrap();
+    /// assert!(success.duration < Duration::from_secs(1));
+    /// # }
+    /// ```
+    pub fn inner_mut(self: Pin<&mut Self>) -> Pin<&mut Fut> {
+        self.project().future
+    }
+
+    /// Stop retrying once `token` is cancelled.
+    ///
+    /// When the token is cancelled, the `Restartable` resolves to [`Failure::Cancelled`] the next
+    /// time it's polled. The inner future is dropped along with the `Restartable`.
+    #[cfg(feature = "use_tokio_util")]
+    pub fn with_cancellation_token(mut self, token: tokio_util::sync::CancellationToken) -> Self {
+        self.cancelled = Some(Box::pin(token.cancelled_owned()));
+        self
+    }
+
+    /// Calls `f` with the test's result for every attempt, whether it passed or failed.
+    ///
+    /// Like [`Iterator::inspect`], this is for debugging: `f` can't change the value, or whether
+    /// the `Restartable` restarts or resolves. It's called before that decision is made.
+    pub fn inspect<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Result<T, E>) + Send + Sync + 'static,
+    {
+        self.inspect = Some(Box::new(move |result, _| f(result)));
+        self
+    }
+
+    /// Like [`Restartable::inspect`], but `f` also gets the context from
+    /// [`Restartable::with_context`].
+    pub fn inspect_with_context<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Result<T, E>, &Ctx) + Send + Sync + 'static,
+    {
+        self.inspect = Some(Box::new(f));
+        self
+    }
+
+    /// Report each attempt, and the final outcome, to `sink` as the `Restartable` runs.
+    ///
+    /// See [`MetricsSink`] for which events are reported, and when. This is a more structured
+    /// alternative to [`Restartable::inspect`], meant for exporting metrics. Pass an `Arc` to keep
+    /// a handle on the sink, or to share it between several `Restartable`s. The durations the sink
+    /// gets come from the `Restartable`'s clock, so setting one makes even a loop without a
+    /// timeout read it.
+    ///
+    /// ```
+    /// use restartables::{CompletionReason, MetricsSink, Restartable};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Default)]
+    /// struct Counts {
+    ///     started: AtomicUsize,
+    ///     failed: AtomicUsize,
+    ///     passed_after_retries: AtomicUsize,
+    /// }
+    ///
+    /// impl<T, E> MetricsSink<T, E> for Counts {
+    ///     fn on_attempt_start(&self, _attempt: usize, _ctx: &()) {
+    ///         self.started.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    ///     fn on_attempt_end(&self, result: &Result<T, E>, _duration: Duration, _ctx: &()) {
+    ///         if result.is_err() {
+    ///             self.failed.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     }
+    ///     fn on_complete(&self, reason: CompletionReason, _: Duration, _: usize, _: &()) {
+    ///         if reason == CompletionReason::RetriedThenPassed {
+    ///             self.passed_after_retries.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let counts = Arc::new(Counts::default());
+    /// let attempts = AtomicUsize::new(0);
+    /// let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    /// let retrying = Restartable::new(factory, Some(Duration::from_secs(1)), |n| {
+    ///     if n == 2 { Ok(n) } else { Err(n) }
+    /// })
+    /// .metrics(counts.clone());
+    /// retrying.await.unwrap();
+    /// assert_eq!(counts.started.load(Ordering::SeqCst), 3);
+    /// assert_eq!(counts.failed.load(Ordering::SeqCst), 2);
+    /// assert_eq!(counts.passed_after_retries.load(Ordering::SeqCst), 1);
+    /// # }
+    /// ```
+    pub fn metrics<S>(mut self, sink: S) -> Self
+    where
+        S: MetricsSink<T, E, Ctx> + Send + Sync + 'static,
+    {
+        self.timed = true;
+        self.metrics = Some(Box::new(sink));
+        self
+    }
+
+    /// Stop retrying once `deadline` passes, as well as when the timeout expires.
+    ///
+    /// This is for nested retries. Share one [`Deadline`] between an outer loop and the inner
+    /// loops it makes, so that the inner loops never run past the outer loop's budget. Passing the
+    /// deadline resolves like the timeout expiring, with [`TimeoutScope::Overall`]. If this is
+    /// called more than once, the earliest deadline wins.
+    ///
+    /// ```
+    /// use restartables::{Deadline, Restartable};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let deadline = Deadline::after(Duration::from_millis(50));
+    /// let start = Instant::now();
+    ///
+    /// // Every inner loop fails. Without the deadline, the first one would run for a minute.
+    /// let inner = || {
+    ///     Restartable::new(
+    ///         || async { tokio::task::yield_now().await },
+    ///         Some(Duration::from_secs(60)),
+    ///         |()| Err::<(), _>("inner failure"),
+    ///     )
+    ///     .deadline(deadline)
+    /// };
+    /// let outer = Restartable::new(inner, None, |inner_outcome| inner_outcome).deadline(deadline);
+    ///
+    /// assert!(outer.await.is_err());
+    /// assert!(start.elapsed() < Duration::from_secs(60));
+    /// # }
+    /// ```
+    pub fn deadline(mut self, deadline: Deadline) -> Self {
+        self.timed = true;
+        self.deadline = Some(match self.deadline {
+            Some(earlier) => earlier.min(deadline),
+            None => deadline,
+        });
+        self
+    }
+
+    /// Read the time from `clock` instead of the system clock, for the timeout, the deadline,
+    /// per-attempt timeouts and every reported duration.
+    ///
+    /// This is for tests: with a fake clock, the loop sees exactly as much time pass as the test
+    /// says, so timing assertions are deterministic and don't need real sleeps. Waits from
+    /// [`Restartable::min_retry_interval`] and [`Restartable::backoff`] still sleep on the
+    /// runtime's timer, though they're cut short using this clock.
+    ///
+    /// ```
+    /// use restartables::{Clock, Failure, Restartable};
+    /// use std::sync::{Arc, Mutex};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// #[derive(Clone)]
+    /// struct FakeClock(Arc<Mutex<Instant>>);
+    ///
+    /// impl Clock for FakeClock {
+    ///     fn now(&self) -> Instant {
+    ///         *self.0.lock().unwrap()
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+    /// // Every attempt takes 10ms of fake time, and fails.
+    /// let handle = clock.clone();
+    /// let slow_failure = move |n: u8| {
+    ///     *handle.0.lock().unwrap() += Duration::from_millis(10);
+    ///     Err::<(), _>(n)
+    /// };
+    /// let retrying = Restartable::new(|| async { 1 }, Some(Duration::from_millis(35)), slow_failure)
+    ///     .clock(clock);
+    /// match retrying.await {
+    ///     Err(Failure::Err { restarts, elapsed, .. }) => {
+    ///         assert_eq!(restarts, 3);
+    ///         assert_eq!(elapsed, Duration::from_millis(40));
+    ///     }
+    ///     other => panic!("expected a timeout, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock + Send + Sync + 'static,
+    {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Restart an attempt if it's still pending after being polled `n` times in a row.
+    ///
+    /// Unlike a timeout, this counts polls, not time. It's useful for inner futures which can get
+    /// stuck, e.g. because they never wake their task. To make sure such an attempt still uses up
+    /// its polls, the `Restartable` wakes itself every time the attempt is pending, so expect it to
+    /// be polled continuously. An abandoned attempt counts as a restart.
+    pub fn max_pending_polls(mut self, n: usize) -> Self {
+        self.max_pending_polls = Some(n);
+        self
+    }
+
+    /// Restart at most `n` times, i.e. make at most `n + 1` attempts, resolving to
+    /// [`Failure::MaxRetries`] if they all fail the test, even if there's time left before the
+    /// timeout.
+    ///
+    /// This replaces any attempt limit from a [`RetryPolicy`] or [`Restartable::retry_n`]. Unlike
+    /// `retry_n`, the timeout still applies, so whichever runs out first stops the loop.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let retrying = Restartable::new(|| async { 3 }, Some(Duration::from_secs(60)), |n: u8| Err::<(), _>(n))
+    ///     .max_restarts(4);
+    /// match retrying.await {
+    ///     Err(Failure::MaxRetries { error, restarts }) => {
+    ///         assert_eq!(error, 3);
+    ///         assert_eq!(restarts, 4);
+    ///     }
+    ///     other => panic!("expected to hit the retry cap, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn max_restarts(mut self, n: usize) -> Self {
+        self.max_attempts = Some(n.saturating_add(1));
+        self.restarts_capped = true;
+        self
+    }
+
+    /// Yield to the executor after every restart, before polling the next attempt.
+    ///
+    /// Normally a restart wakes the task and the new attempt is polled straight away, so a tight
+    /// failure loop can hog a single-threaded executor. Yielding gives other tasks a turn first.
+    /// With the `use_tokio` feature this uses [`tokio::task::yield_now`], otherwise it returns
+    /// `Poll::Pending` once more, which works on any executor.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main(flavor = "current_thread")]
+    /// # async fn main() {
+    /// let ready = Arc::new(AtomicBool::new(false));
+    /// let setter = ready.clone();
+    /// tokio::spawn(async move { setter.store(true, Ordering::SeqCst) });
+    ///
+    /// // The spawned task shares this thread, so it only runs when the retry loop yields.
+    /// let factory = || async { ready.load(Ordering::SeqCst) };
+    /// let retrying = Restartable::new(factory, Some(Duration::from_secs(1)), |ready| {
+    ///     if ready { Ok(()) } else { Err(()) }
+    /// })
+    /// .yield_between_attempts(true);
+    /// assert!(retrying.await.is_ok());
+    /// # }
+    /// ```
+    pub fn yield_between_attempts(mut self, enabled: bool) -> Self {
+        self.yield_between_attempts = enabled;
+        self
+    }
+
+    /// Wait at least `interval` after an attempt fails before starting the next one.
+    ///
+    /// By default there's no wait at all: a restart wakes the task and the next attempt is polled
+    /// straight away. If attempts fail quickly, e.g. a connection is refused, the loop spins,
+    /// using a whole CPU core until it succeeds or times out. This is a simple guard against that,
+    /// for when a full [`backoff`](Restartable::backoff) strategy would be overkill. The wait
+    /// counts towards the timeout.
+    ///
+    /// With the `use_tokio` feature, inside a tokio runtime, this uses [`tokio::time::sleep`].
+    /// Otherwise, with `use_async_std` it uses `async_std::task::sleep`, and without it, it sleeps
+    /// on one background timer thread, shared by every `Restartable`, which works on any
+    /// executor. Dropping the `Restartable` cancels the sleep.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let start = Instant::now();
+    /// let refused = || async { Err::<(), _>("connection refused") };
+    /// let retrying = Restartable::retry_n(refused, |result| result, 3)
+    ///     .min_retry_interval(Duration::from_millis(10));
+    /// assert!(retrying.await.is_err());
+    /// // Three attempts, so two waits.
+    /// assert!(start.elapsed() >= Duration::from_millis(20));
+    /// # }
+    /// ```
+    pub fn min_retry_interval(mut self, interval: Duration) -> Self {
+        self.min_retry_interval = Some(interval);
+        self
+    }
+
+    /// Before each restart, ask `stop` whether to stop retrying, and stop if its future resolves
+    /// to `true`.
+    ///
+    /// This is for stop conditions outside the attempts themselves, like "stop once the deploy is
+    /// marked complete elsewhere". `stop` is called after every attempt which doesn't end the
+    /// loop, and the next attempt waits for its future, which counts towards the timeout. If it
+    /// says to stop, the loop resolves with the most recent value which passed the test, if it
+    /// held on to one, e.g. from [`Resolution::AcceptButContinue`] or
+    /// [`require_success_rate`](Restartable::require_success_rate). Otherwise it fails with
+    /// [`Failure::StopRequested`].
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let deploy_done = Arc::new(AtomicBool::new(false));
+    /// let done = deploy_done.clone();
+    /// let outcome = Restartable::new(
+    ///     || async { Err::<(), _>("canary unhealthy") },
+    ///     None,
+    ///     |result| result,
+    /// )
+    /// .until(move || {
+    ///     let done = done.clone();
+    ///     async move { done.load(Ordering::SeqCst) }
+    /// })
+    /// .inspect(move |_| deploy_done.store(true, Ordering::SeqCst))
+    /// .await;
+    /// assert!(matches!(outcome, Err(Failure::StopRequested { restarts: 1 })));
+    /// # }
+    /// ```
+    pub fn until<F, StopFut>(mut self, stop: F) -> Self
+    where
+        F: Fn() -> StopFut + Send + Sync + 'static,
+        StopFut: Future<Output = bool> + Send + Sync + 'static,
+    {
+        self.until = Some(Box::new(move || Box::pin(stop())));
+        self
+    }
+
+    /// Before each restart, wait for the future from `gate` to resolve.
+    ///
+    /// This is for backpressure from outside the retry loop, e.g. waiting until a connection pool
+    /// has capacity, or until a reconnect notification arrives. `gate` is called after every
+    /// restart, and the new attempt isn't polled until the future it returns resolves. The new
+    /// attempt's future is built straight away, but futures do nothing until they're polled. Time
+    /// spent waiting counts towards the overall timeout, but not the per-attempt timeout from
+    /// [`Restartable::attempt_timeout_fn`]. Like the timeout, it's only checked when the
+    /// `Restartable` is polled, which is when the gate wakes it.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use tokio::sync::Semaphore;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Each retry needs a permit, and they're handed out one at a time.
+    /// let permits = Arc::new(Semaphore::new(0));
+    /// let gate_permits = permits.clone();
+    /// let attempts = AtomicUsize::new(0);
+    /// let retrying = Restartable::new(
+    ///     || async { attempts.fetch_add(1, Ordering::SeqCst) },
+    ///     Some(Duration::from_secs(1)),
+    ///     |n| if n == 2 { Ok(n) } else { Err(n) },
+    /// )
+    /// .gate_next_attempt(move || {
+    ///     let permits = gate_permits.clone();
+    ///     async move { permits.acquire().await.unwrap().forget() }
+    /// });
+    /// tokio::spawn(async move {
+    ///     for _ in 0..2 {
+    ///         tokio::time::sleep(Duration::from_millis(10)).await;
+    ///         permits.add_permits(1);
+    ///     }
+    /// });
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.restarts, 2);
+    /// assert!(success.duration >= Duration::from_millis(20));
+    /// # }
+    /// ```
+    pub fn gate_next_attempt<F, GateFut>(mut self, gate: F) -> Self
+    where
+        F: Fn() -> GateFut + Send + Sync + 'static,
+        GateFut: Future<Output = ()> + Send + Sync + 'static,
+    {
+        self.gate = Some(Box::new(move || Box::pin(gate())));
+        self
+    }
+
+    /// Hold off while `pauser` is paused.
+    ///
+    /// While paused, the `Restartable` stays pending without polling the current attempt, and is
+    /// woken when the pauser resumes. Time spent paused isn't counted towards the overall timeout,
+    /// or `Success::duration`. A [`Deadline`] is a fixed point in time, so it isn't extended.
+    /// Cancellation still works while paused.
+    ///
+    /// ```
+    /// use restartables::{Pauser, Restartable};
+    /// use std::time::{Duration, Instant};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let pauser = Pauser::new();
+    /// pauser.pause();
+    /// let resumer = pauser.clone();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_millis(50)).await;
+    ///     resumer.resume();
+    /// });
+    ///
+    /// // The pause is longer than the timeout, but doesn't count towards it.
+    /// let start = Instant::now();
+    /// let retrying = Restartable::new(|| async { 1 }, Some(Duration::from_millis(20)), Ok::<_, ()>)
+    ///     .with_pauser(pauser);
+    /// let success = retrying.await.unwrap();
+    /// assert!(start.elapsed() >= Duration::from_millis(50));
+    /// assert!(success.duration < Duration::from_millis(20));
+    /// # }
+    /// ```
+    pub fn with_pauser(mut self, pauser: Pauser) -> Self {
+        self.pauser = Some((pauser, Duration::ZERO));
+        self
+    }
+
+    /// Acknowledge that this `Restartable` may retry forever, which silences the warning about it.
+    ///
+    /// A `Restartable` with no timeout, deadline, attempt limit, repeated-error limit, `until`
+    /// predicate or cancellation can only stop when an attempt passes the test. The first time
+    /// one is polled, a warning is printed to stderr in debug builds, once per process. With the
+    /// `use_log` feature, it's logged with `log::warn!` instead, in release builds too. Call this
+    /// if retrying forever is intended, e.g. for a loop which is only ever stopped by dropping it.
+    pub fn allow_unbounded(mut self) -> Self {
+        self.allow_unbounded = true;
+        self
+    }
+
+    /// Give up early if the test fails with the same error `n` times in a row.
+    ///
+    /// An error which keeps recurring unchanged usually means the failure is deterministic, so
+    /// retrying until the timeout is pointless. On the `n`th identical error in a row, the
+    /// `Restartable` resolves to [`Failure::Err`] with that error. Errors are compared with
+    /// `PartialEq`. Attempts which pass the test, or which are abandoned before they resolve,
+    /// don't break the run.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let retrying = Restartable::new(
+    ///     || async { "not a number" },
+    ///     Some(Duration::from_secs(60)),
+    ///     |s: &str| s.parse::<u8>().map_err(|e| e.to_string()),
+    /// )
+    /// .give_up_on_repeated_error(3);
+    /// match retrying.await {
+    ///     Err(Failure::Err { restarts, .. }) => assert_eq!(restarts, 2),
+    ///     other => panic!("expected to give up, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn give_up_on_repeated_error(mut self, n: usize) -> Self
+    where
+        E: PartialEq + Send + Sync + 'static,
+    {
+        self.repeated = Some(Box::new(Run {
+            n,
+            count: 0,
+            last: None,
+        }));
+        self
+    }
+
+    /// Only retry errors which `retryable` says are worth it, and give up straight away on any
+    /// other.
+    ///
+    /// Some errors are permanent, e.g. malformed input, so retrying them until the timeout is
+    /// pointless. When an attempt fails the test with an error that `retryable` returns `false`
+    /// for, the `Restartable` resolves to [`Failure::NonRetryable`] with that error, without
+    /// starting another attempt, even if there's no time or attempts left.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::sync::atomic::{AtomicU16, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // The server is unavailable, then rejects the request as malformed.
+    /// let statuses = [503, 400, 200];
+    /// let calls = AtomicU16::new(0);
+    /// let call = || async { statuses[calls.fetch_add(1, Ordering::SeqCst) as usize] };
+    /// let is_ok = |status| if status == 200 { Ok(()) } else { Err(status) };
+    /// let retrying = Restartable::new(call, Some(Duration::from_secs(60)), is_ok)
+    ///     .retry_if(|status| *status >= 500);
+    /// match retrying.await {
+    ///     Err(Failure::NonRetryable { error, restarts, .. }) => {
+    ///         assert_eq!(error, 400);
+    ///         assert_eq!(restarts, 1);
+    ///     }
+    ///     other => panic!("expected to give up, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn retry_if<F>(mut self, retryable: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.retryable = Some(Box::new(retryable));
+        self
+    }
+
+    /// Before polling each attempt's future, run a cheap probe, and only go on to the attempt if
+    /// the probe passes.
+    ///
+    /// This is for expensive operations which shouldn't run against a backend that isn't ready.
+    /// Every attempt becomes two stages: first `probe` is called, and the future it returns is
+    /// polled to completion. If it resolves to `Ok(())`, the attempt's own future is polled, and its
+    /// output is tested as usual. If it resolves to `Err`, the attempt fails with that error, as if
+    /// the test had failed, without the attempt's future ever being polled. The attempt's future
+    /// is still built by the factory, but futures do nothing until they're polled.
+    ///
+    /// The probe is part of the attempt, so it counts towards the overall and per-attempt timeouts,
+    /// and towards [`Restartable::max_pending_polls`].
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // The database accepts connections from the third check onwards.
+    /// let checks = Arc::new(AtomicUsize::new(0));
+    /// let migrations = AtomicUsize::new(0);
+    /// let migrate = || async {
+    ///     migrations.fetch_add(1, Ordering::SeqCst);
+    ///     Ok::<_, &str>("migrated")
+    /// };
+    /// let db = checks.clone();
+    /// let retrying = Restartable::from_try_future(migrate, Some(Duration::from_secs(1)))
+    ///     .probe_with(move || {
+    ///         let db = db.clone();
+    ///         async move {
+    ///             match db.fetch_add(1, Ordering::SeqCst) {
+    ///                 0 | 1 => Err("database not ready"),
+    ///                 _ => Ok(()),
+    ///             }
+    ///         }
+    ///     });
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.restarts, 2);
+    /// // The migration only ran once the database was ready.
+    /// assert_eq!(migrations.load(Ordering::SeqCst), 1);
+    /// # }
+    /// ```
+    pub fn probe_with<F, ProbeFut>(mut self, probe: F) -> Self
+    where
+        F: Fn() -> ProbeFut + Send + Sync + 'static,
+        ProbeFut: Future<Output = Result<(), E>> + Send + Sync + 'static,
+    {
+        self.probing = Some(Box::pin(probe()));
+        self.probe = Some(Box::new(move || Box::pin(probe())));
+        self
+    }
+
+    /// Once a value passes the test, run a confirmation before resolving to it.
+    ///
+    /// This is for eventually-consistent systems, where a single passing read might be stale.
+    /// `confirm` is called each time a value passes the test, and the future it returns is polled
+    /// to completion. If it resolves to `Ok(())`, the `Restartable` resolves to the value. If it
+    /// resolves to `Err`, the value is thrown away and the future is restarted, as if the test had
+    /// failed with that error. Values accepted with [`Resolution::AcceptButContinue`] aren't
+    /// confirmed.
+    ///
+    /// The confirmation counts towards the overall timeout, and `Success::duration` includes it.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // The write is only visible to every replica after the third read.
+    /// let reads = Arc::new(AtomicUsize::new(0));
+    /// let replica = reads.clone();
+    /// let retrying = Restartable::new(
+    ///     || async { reads.fetch_add(1, Ordering::SeqCst) },
+    ///     Some(Duration::from_secs(1)),
+    ///     |n| if n >= 1 { Ok(n) } else { Err("stale") },
+    /// )
+    /// .confirm_with(move || {
+    ///     let replica = replica.clone();
+    ///     async move {
+    ///         if replica.load(Ordering::SeqCst) >= 3 { Ok(()) } else { Err("replica is stale") }
+    ///     }
+    /// });
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.value, 2);
+    /// assert_eq!(success.restarts, 2);
+    /// # }
+    /// ```
+    pub fn confirm_with<F, ConfirmFut>(mut self, confirm: F) -> Self
+    where
+        F: Fn() -> ConfirmFut + Send + Sync + 'static,
+        ConfirmFut: Future<Output = Result<(), E>> + Send + Sync + 'static,
+    {
+        self.confirm = Some(Box::new(move || Box::pin(confirm())));
+        self
+    }
+
+    /// Restart an attempt if it's still pending after a timeout computed from its index.
+    ///
+    /// `f` is called with the attempt's index (0 for the first attempt, 1 for the first restart,
+    /// and so on) when the attempt starts. An attempt which runs for longer than that is dropped
+    /// and restarted, counting as a restart. This is separate from the overall timeout, which still
+    /// applies. Returning a very large duration effectively disables the per-attempt bound.
+    ///
+    /// Like the overall timeout, this is only checked when the `Restartable` is polled. If the
+    /// attempt that timed out was the last one allowed by [`Restartable::retry_n`], the
+    /// `Restartable` resolves to [`Failure::Timeout`] with [`TimeoutScope::Attempt`].
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable, TimeoutScope};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Never resolves, but keeps waking its task, so the timeout gets checked.
+    /// let never = || async {
+    ///     loop {
+    ///         tokio::task::yield_now().await;
+    ///     }
+    /// };
+    /// let retrying = Restartable::retry_n(never, |()| Ok::<_, ()>(()), 2)
+    ///     .attempt_timeout_fn(|_| Duration::from_millis(5));
+    /// match retrying.await {
+    ///     Err(Failure::Timeout { restarts, scope, .. }) => {
+    ///         assert_eq!(restarts, 1);
+    ///         assert_eq!(scope, TimeoutScope::Attempt);
+    ///     }
+    ///     other => panic!("expected an attempt timeout, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Cancellation
+    ///
+    /// A timed-out attempt isn't left running. It's dropped in place and replaced with a fresh
+    /// future from the factory, before the new attempt is polled. Dropping a future cancels
+    /// whatever it was doing, so e.g. a slow request's connection is released:
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// // Counts how many attempts have been dropped.
+    /// struct DropGuard<'a>(&'a AtomicUsize);
+    /// impl Drop for DropGuard<'_> {
+    ///     fn drop(&mut self) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let started = AtomicUsize::new(0);
+    /// let dropped = AtomicUsize::new(0);
+    /// let slow = || async {
+    ///     let attempt = started.fetch_add(1, Ordering::SeqCst);
+    ///     // Every earlier attempt timed out, and was dropped before this one was polled.
+    ///     assert_eq!(dropped.load(Ordering::SeqCst), attempt);
+    ///     let _guard = DropGuard(&dropped);
+    ///     loop {
+    ///         tokio::task::yield_now().await;
+    ///     }
+    /// };
+    /// let retrying = Restartable::retry_n(slow, |()| Ok::<_, ()>(()), 3)
+    ///     .attempt_timeout_fn(|_| Duration::from_millis(5));
+    /// assert!(retrying.await.is_err());
+    /// assert_eq!(started.load(Ordering::SeqCst), 3);
+    /// assert_eq!(dropped.load(Ordering::SeqCst), 3);
+    /// # }
+    /// ```
+    pub fn attempt_timeout_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(usize) -> Duration + Send + Sync + 'static,
+    {
+        self.timed = true;
+        self.attempt_limit = Some(f(self.restarts));
+        self.attempt_timeout = Some(Box::new(f));
+        self
+    }
+
+    /// Resolve on the first value that passes the test, but keep retrying in the background.
+    ///
+    /// This is useful for e.g. cache warming, where the caller wants _a_ value as soon as possible,
+    /// but a later attempt might find a better one. After the first success, fresh retry loops
+    /// (built from clones of the factory and test) are run on `spawner`, and every value they find
+    /// is passed to `on_value`. Return `false` from `on_value` to stop retrying.
+    ///
+    /// The background loops carry on with this `Restartable`'s clock, attempt limit,
+    /// `max_pending_polls`, `min_retry_interval`, attempt timeout and backoff, including any
+    /// filled in from the global defaults. They share the one backoff, since only one runs at a
+    /// time. The other hooks, like `inspect`, `metrics` and `until`, stay with this `Restartable`.
+    /// After each value, the next loop waits for the `min_retry_interval` before starting.
+    ///
+    /// The background work is fire-and-forget: nothing waits for it, and its failures are
+    /// silently dropped. It stops when `on_value` returns `false`, when a loop gives up, or when
+    /// whatever remains of this `Restartable`'s timeout expires. With a `None` timeout and an
+    /// `on_value` that always returns `true`, it runs forever, and without a `min_retry_interval`,
+    /// each loop starts as soon as the last one passed, so set one to keep it from spinning.
+    pub fn return_first_and_continue<S, F>(mut self, spawner: S, on_value: F) -> Self
+    where
+        Fut: Send + 'static,
+        Factory: Clone + Send + Sync + 'static,
+        Test: Clone + Send + Sync + 'static,
+        T: Send + 'static,
+        E: 'static,
+        S: Spawner + Send + Sync + 'static,
+        F: Fn(Success<T>) -> bool + Send + Sync + 'static,
+    {
+        let factory = self.factory.clone();
+        let test = self.test.clone();
+        self.background = Some(Box::new(move |carried| {
+            spawner.spawn(Box::pin(spawn::keep_retrying(
+                factory, test, on_value, carried,
+            )))
+        }));
+        self
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Future for Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    type Output = Outcome<T, E, Raw>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.timed && self.start.is_none() && self.is_unbounded() {
+            warn_unbounded();
+        }
         let mut this = self.project();
-        let start = this.start.get_or_insert_with(Instant::now);
+        if *this.timed && this.start.is_none() {
+            *this.start = Some(this.clock.now());
+            if let Some((pauser, paused_before_start)) = this.pauser.as_mut() {
+                *paused_before_start = pauser.paused_total();
+            }
+        }
 
-        // Call the inner poll, run the result through `self.test`.
-        let inner_poll = this.future.as_mut().poll(cx).map(this.test);
+        // Check for cancellation before polling the inner future, so a cancelled loop stops
+        // without starting any more work.
+        if let Some(cancelled) = this.cancelled.as_mut() {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                let restarts = *this.restarts;
+                return Poll::Ready(this.fail(Failure::Cancelled { restarts }));
+            }
+        }
 
-        // Measure timing
-        let elapsed = start.elapsed();
-        let timed_out = if let Some(timeout) = *this.timeout {
-            elapsed > timeout
+        // Do nothing at all while paused.
+        if let Some((pauser, _)) = this.pauser.as_ref() {
+            if pauser.poll_resumed(cx).is_pending() {
+                return Poll::Pending;
+            }
+        }
+
+        // Ask whether to stop before starting the next attempt.
+        if let Some(stopping) = this.stopping.as_mut() {
+            match stopping.as_mut().poll(cx) {
+                Poll::Ready(true) => {
+                    *this.stopping = None;
+                    return Poll::Ready(this.stop_requested());
+                }
+                Poll::Ready(false) => *this.stopping = None,
+                Poll::Pending => {
+                    let elapsed = this.elapsed();
+                    if this.timed_out(elapsed) {
+                        let restarts = *this.restarts;
+                        let scope = TimeoutScope::Overall;
+                        return Poll::Ready(this.fail(Failure::Timeout {
+                            restarts,
+                            scope,
+                            elapsed,
+                        }));
+                    }
+                    return Poll::Pending;
+                }
+            }
+        }
+
+        // Wait out the minimum interval since the last attempt failed.
+        if let Some(delaying) = this.delaying.as_mut() {
+            if delaying.as_mut().poll(cx).is_pending() {
+                let elapsed = this.elapsed();
+                if this.timed_out(elapsed) {
+                    let restarts = *this.restarts;
+                    let scope = TimeoutScope::Overall;
+                    return Poll::Ready(this.fail(Failure::Timeout {
+                        restarts,
+                        scope,
+                        elapsed,
+                    }));
+                }
+                return Poll::Pending;
+            }
+            *this.delaying = None;
+        }
+
+        // Wait for the gate to open before starting the next attempt.
+        if let Some(gating) = this.gating.as_mut() {
+            if gating.as_mut().poll(cx).is_pending() {
+                let elapsed = this.elapsed();
+                if this.timed_out(elapsed) {
+                    let restarts = *this.restarts;
+                    let scope = TimeoutScope::Overall;
+                    return Poll::Ready(this.fail(Failure::Timeout {
+                        restarts,
+                        scope,
+                        elapsed,
+                    }));
+                }
+                return Poll::Pending;
+            }
+            *this.gating = None;
+        }
+
+        // Give other tasks a turn before starting the next attempt.
+        if let Some(yielding) = this.yielding.as_mut() {
+            if yielding.as_mut().poll(cx).is_pending() {
+                return Poll::Pending;
+            }
+            *this.yielding = None;
+        }
+
+        // Only start the attempt's clock once it's actually being polled.
+        let attempt_start = if *this.timed {
+            let clock = &*this.clock;
+            Some(*this.attempt_start.get_or_insert_with(|| clock.now()))
         } else {
-            false
+            None
+        };
+
+        let out_of_attempts =
+            matches!(*this.max_attempts, Some(max) if this.restarts.saturating_add(1) >= max);
+
+        // A value already passed the test, and is waiting on its confirmation.
+        if let Some((confirming, _)) = this.confirming.as_mut() {
+            let confirmed = confirming.as_mut().poll(cx);
+            let elapsed = this.elapsed();
+            let timed_out = this.timed_out(elapsed);
+            if confirmed.is_ready() || timed_out {
+                let (_, mut success) = this.confirming.take().unwrap();
+                success.duration = elapsed;
+                return match (confirmed, timed_out) {
+                    (Poll::Ready(Ok(())), _) => Poll::Ready(Ok(this.succeed(success, elapsed))),
+                    (Poll::Pending, _) => {
+                        let restarts = *this.restarts;
+                        let scope = TimeoutScope::Overall;
+                        Poll::Ready(this.fail(Failure::Timeout {
+                            restarts,
+                            scope,
+                            elapsed,
+                        }))
+                    }
+                    (Poll::Ready(Err(error)), false) if out_of_attempts => {
+                        let failure = this.out_of_attempts(error);
+                        Poll::Ready(this.fail(failure))
+                    }
+                    (Poll::Ready(Err(error)), false) => match this.next_backoff(Some(&error)) {
+                        Some(backoff) => {
+                            this.restart_after_failure(cx, backoff);
+                            Poll::Pending
+                        }
+                        None => {
+                            let restarts = *this.restarts;
+                            Poll::Ready(this.fail(Failure::Exhausted { error, restarts }))
+                        }
+                    },
+                    (Poll::Ready(Err(error)), true) => {
+                        let restarts = *this.restarts;
+                        let (recent_errors, dropped_errors) = this.recent_errors();
+                        Poll::Ready(this.fail(Failure::Err {
+                            error,
+                            restarts,
+                            elapsed,
+                            recent_errors,
+                            dropped_errors,
+                        }))
+                    }
+                };
+            }
+            return Poll::Pending;
+        }
+
+        if let Some(metrics) = this.metrics.as_ref() {
+            if !*this.attempt_reported {
+                *this.attempt_reported = true;
+                metrics.on_attempt_start(*this.restarts, this.ctx);
+            }
+        }
+
+        // Call the inner poll, run the result through `self.test`. If there's a probe, the inner
+        // future isn't polled until it passes, and a failed probe fails the attempt.
+        let mut keep_going = false;
+        let mut raw_output = None;
+        let mut factory_failed = false;
+        let probed = match this
+            .probing
+            .as_mut()
+            .map(|probing| probing.as_mut().poll(cx))
+        {
+            Some(Poll::Ready(Ok(()))) => {
+                *this.probing = None;
+                Poll::Ready(Ok(()))
+            }
+            Some(probed) => probed,
+            None => Poll::Ready(Ok(())),
+        };
+        let mut inner_poll = match probed {
+            Poll::Ready(Ok(())) => this.future.as_mut().poll(cx).map(|output| {
+                raw_output = (this.keep_raw)(&output);
+                factory_failed = (this.factory_failed)(&output);
+                let (result, provisional) =
+                    this.test.resolve_attempt(output, *this.restarts).split();
+                keep_going = provisional;
+                result
+            }),
+            Poll::Ready(Err(error)) => Poll::Ready(Err(error)),
+            Poll::Pending => Poll::Pending,
         };
+        // A factory error means no attempt ran, so it skips everything which sees an attempt's
+        // result, and fails with `Failure::FactoryError` below.
+        let mut rate_unmet = false;
+        if !factory_failed {
+            if let (Poll::Ready(result), Some(inspect)) = (&inner_poll, this.inspect.as_ref()) {
+                inspect(result, this.ctx);
+            }
+            if let Poll::Ready(result) = &inner_poll {
+                let attempt_elapsed = attempt_start.map_or(Duration::ZERO, |start| {
+                    this.clock.now().saturating_duration_since(start)
+                });
+                this.attempt_durations.push(attempt_elapsed);
+                if let (Err(error), Some(recent)) = (result, this.recent_errors.as_mut()) {
+                    recent.record(error);
+                }
+                if let Some(metrics) = this.metrics.as_ref() {
+                    metrics.on_attempt_end(result, attempt_elapsed, this.ctx);
+                    *this.attempt_reported = false;
+                }
+            }
+
+            // With a success rate, a pass only counts once the rate is met, and once it is, even a
+            // failure resolves with the most recent pass.
+            if let (Poll::Ready(result), Some(rate)) = (&inner_poll, this.success_rate.as_mut()) {
+                rate.record(result.is_ok());
+                if !rate.is_met() {
+                    rate_unmet = result.is_ok();
+                } else if result.is_err() {
+                    if let Some((value, raw)) = rate.latest.take() {
+                        inner_poll = Poll::Ready(Ok(value));
+                        raw_output = raw;
+                    }
+                }
+            }
+        }
+
+        // Measure timing
+        let elapsed = this.elapsed();
+        let timed_out = this.timed_out(elapsed);
 
         match (inner_poll, timed_out) {
             // Inner future timed out without ever resolving
-            (Poll::Pending, true) => Poll::Ready(Err(Failure::Timeout)),
-            // There's still time to poll again
-            (Poll::Pending, false) => Poll::Pending,
-            // Success!
-            (Poll::Ready(Ok(resp)), _) => Poll::Ready(Ok(Success {
-                value: resp,
-                duration: elapsed,
-                restarts: *this.restarts,
-            })),
-            // Failure, but there's still time to restart the future and try again.
-            (Poll::Ready(Err(_)), false) => {
-                cx.waker().wake_by_ref();
-                let new_future = (this.factory)();
-                this.future.set(new_future);
-                *this.restarts += 1;
+            (Poll::Pending, true) => {
+                let restarts = *this.restarts;
+                let scope = TimeoutScope::Overall;
+                Poll::Ready(this.fail(Failure::Timeout {
+                    restarts,
+                    scope,
+                    elapsed,
+                }))
+            }
+            // There's still time to poll again, unless this attempt has been pending too long.
+            (Poll::Pending, false) => {
+                let attempt_timed_out = match (*this.attempt_limit, attempt_start) {
+                    (Some(limit), Some(attempt_start)) => {
+                        this.clock.now().saturating_duration_since(attempt_start) > limit
+                    }
+                    _ => false,
+                };
+                let abandoned = attempt_timed_out
+                    || match *this.max_pending_polls {
+                        Some(max) => {
+                            *this.pending_polls = this.pending_polls.saturating_add(1);
+                            *this.pending_polls >= max
+                        }
+                        None => false,
+                    };
+                if abandoned {
+                    // Give up if that was the last attempt allowed, or the backoff says to stop.
+                    let backoff = if out_of_attempts {
+                        None
+                    } else {
+                        this.next_backoff(None)
+                    };
+                    match backoff {
+                        Some(backoff) => this.restart_after_failure(cx, backoff),
+                        None => {
+                            let restarts = *this.restarts;
+                            let scope = TimeoutScope::Attempt;
+                            return Poll::Ready(this.fail(Failure::Timeout {
+                                restarts,
+                                scope,
+                                elapsed,
+                            }));
+                        }
+                    }
+                } else if this.max_pending_polls.is_some() {
+                    // Keep ticking, so an attempt which never wakes still uses up its polls.
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            }
+            // The factory couldn't build this attempt, so there's nothing to retry.
+            (Poll::Ready(Err(error)), _) if factory_failed => {
+                let restarts = *this.restarts;
+                Poll::Ready(this.fail(Failure::FactoryError { error, restarts }))
+            }
+            // Passed, but not often enough yet.
+            (Poll::Ready(Ok(resp)), timed_out) if rate_unmet => {
+                if let Some(rate) = this.success_rate.as_mut() {
+                    rate.latest = Some((resp, raw_output));
+                }
+                let restarts = *this.restarts;
+                if timed_out {
+                    let scope = TimeoutScope::Overall;
+                    return Poll::Ready(this.fail(Failure::Timeout {
+                        restarts,
+                        scope,
+                        elapsed,
+                    }));
+                }
+                // Back off before the next attempt, just like after a failure, but without counting
+                // this pass as one for `timeout_growth`.
+                let backoff = if out_of_attempts {
+                    None
+                } else {
+                    this.next_backoff(None)
+                };
+                match backoff {
+                    Some(backoff) => {
+                        this.passed_but_restarting();
+                        let backoff = Some(backoff).filter(|backoff| !backoff.is_zero());
+                        this.restart_with_delay(cx, backoff);
+                        Poll::Pending
+                    }
+                    None => Poll::Ready(this.fail(Failure::SuccessRateUnmet { restarts })),
+                }
+            }
+            // Good enough for now, but there's still time to look for something better.
+            (Poll::Ready(Ok(resp)), false) if keep_going => {
+                *this.provisional = Some(Success {
+                    value: resp,
+                    duration: elapsed,
+                    restarts: *this.restarts,
+                    raw_output,
+                    attempt_durations: Vec::new(),
+                });
+                this.passed_but_restarting();
+                this.restart(cx);
                 Poll::Pending
             }
+            // Success!
+            (Poll::Ready(Ok(resp)), _) => {
+                let success = Success {
+                    value: resp,
+                    duration: elapsed,
+                    restarts: *this.restarts,
+                    raw_output,
+                    attempt_durations: Vec::new(),
+                };
+                match this.confirm.as_ref() {
+                    // Confirm it before resolving. Poll the confirmation straight away.
+                    Some(confirm) => {
+                        *this.confirming = Some((confirm(), success));
+                        cx.waker().wake_by_ref();
+                        Poll::Pending
+                    }
+                    None => Poll::Ready(Ok(this.succeed(success, elapsed))),
+                }
+            }
+            // Failure, and the error isn't worth retrying, whether or not there's time left.
+            (Poll::Ready(Err(e)), _)
+                if this
+                    .retryable
+                    .as_ref()
+                    .is_some_and(|retryable| !retryable(&e)) =>
+            {
+                let restarts = *this.restarts;
+                let (recent_errors, dropped_errors) = this.recent_errors();
+                Poll::Ready(this.fail(Failure::NonRetryable {
+                    error: e,
+                    restarts,
+                    elapsed,
+                    recent_errors,
+                    dropped_errors,
+                }))
+            }
+            // Failure, and that was the last attempt allowed.
+            (Poll::Ready(Err(e)), false) if out_of_attempts => {
+                let failure = this.out_of_attempts(e);
+                Poll::Ready(this.fail(failure))
+            }
+            // Failure, and the same error keeps coming back, so give up before asking the backoff.
+            (Poll::Ready(Err(e)), false)
+                if this.repeated.as_mut().is_some_and(|r| r.is_repeat(&e)) =>
+            {
+                let restarts = *this.restarts;
+                let (recent_errors, dropped_errors) = this.recent_errors();
+                Poll::Ready(this.fail(Failure::Err {
+                    error: e,
+                    restarts,
+                    elapsed,
+                    recent_errors,
+                    dropped_errors,
+                }))
+            }
+            // Failure, but there's still time to restart the future and try again, unless the
+            // backoff says to stop.
+            (Poll::Ready(Err(e)), false) => match this.next_backoff(Some(&e)) {
+                Some(backoff) => {
+                    if let Some(repeated) = this.repeated.as_mut() {
+                        repeated.remember(e);
+                    }
+                    this.restart_after_failure(cx, backoff);
+                    Poll::Pending
+                }
+                None => {
+                    let restarts = *this.restarts;
+                    Poll::Ready(this.fail(Failure::Exhausted { error: e, restarts }))
+                }
+            },
             // Failure, and the timeout has expired, so return the failure.
-            (Poll::Ready(Err(e)), true) => Poll::Ready(Err(Failure::Err {
-                error: e,
-                restarts: *this.restarts,
-            })),
+            (Poll::Ready(Err(e)), true) => {
+                let restarts = *this.restarts;
+                let (recent_errors, dropped_errors) = this.recent_errors();
+                Poll::Ready(this.fail(Failure::Err {
+                    error: e,
+                    restarts,
+                    elapsed,
+                    recent_errors,
+                    dropped_errors,
+                }))
+            }
         }
     }
 }
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> RestartableProj<'_, Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// How long to back off after the current attempt, or `None` if the backoff says to stop
+    /// retrying. `error` is the test's error, if it failed, for `backoff_for`. Without a backoff,
+    /// there's no wait.
+    fn next_backoff(&mut self, error: Option<&E>) -> Option<Duration> {
+        let position = *self.backoff_position;
+        *self.backoff_position = position.saturating_add(1);
+        if let (Some(backoff_for), Some(error)) = (self.backoff_for.as_ref(), error) {
+            return backoff_for(error, position);
+        }
+        match self.backoff.as_mut() {
+            Some(backoff) => backoff.next_delay(position),
+            None => Some(Duration::ZERO),
+        }
+    }
+
+    /// Like `restart`, but after the current attempt failed, rather than passed, so it backs off
+    /// for `backoff`, from `next_backoff`.
+    fn restart_after_failure(&mut self, cx: &mut Context, backoff: Duration) {
+        *self.consecutive_passes = 0;
+        if let Some(growth) = self.timeout_growth.as_mut() {
+            growth.failed();
+        }
+        let backoff = Some(backoff).filter(|backoff| !backoff.is_zero());
+        self.restart_with_delay(cx, backoff);
+    }
+
+    /// Counts an attempt which passed the test, but is restarted anyway, and resets the backoff
+    /// once enough have passed in a row for `reset_backoff_after_success`.
+    fn passed_but_restarting(&mut self) {
+        *self.consecutive_passes = self.consecutive_passes.saturating_add(1);
+        if matches!(*self.reset_backoff_after, Some(n) if *self.consecutive_passes >= n) {
+            *self.backoff_position = 0;
+        }
+    }
+
+    /// Tells the metrics sink that the current attempt is being dropped before it resolved, if
+    /// it was started and hasn't already ended.
+    fn abandon_attempt(&mut self) {
+        if !std::mem::take(self.attempt_reported) {
+            return;
+        }
+        if let Some(metrics) = self.metrics.as_ref() {
+            let clock = &*self.clock;
+            let duration = self.attempt_start.map_or(Duration::ZERO, |start| {
+                clock.now().saturating_duration_since(start)
+            });
+            metrics.on_attempt_abandoned(*self.restarts, duration, self.ctx);
+        }
+    }
+
+    /// Replaces the current attempt with a fresh future, and schedules it to be polled.
+    fn restart(&mut self, cx: &mut Context) {
+        self.restart_with_delay(cx, None);
+    }
+
+    /// Like `restart`, but waits for at least `backoff` before the new attempt.
+    fn restart_with_delay(&mut self, cx: &mut Context, backoff: Option<Duration>) {
+        cx.waker().wake_by_ref();
+        self.abandon_attempt();
+        let new_future = (self.factory)();
+        // `Pin::set` drops the old future in place, so this is sound for `!Unpin` futures. It also
+        // cancels an abandoned attempt, rather than leaving it running.
+        self.future.set(new_future);
+        // Saturate rather than overflow, since a loop with no timeout can run forever.
+        *self.restarts = self.restarts.saturating_add(1);
+        *self.pending_polls = 0;
+        *self.attempt_reported = false;
+        *self.attempt_start = None;
+        *self.attempt_limit = self.attempt_timeout.as_ref().map(|f| f(*self.restarts));
+        if let (Some(limit), Some(growth)) = (self.attempt_limit.as_mut(), *self.timeout_growth) {
+            *limit = growth.apply(*limit);
+        }
+        *self.stopping = self.until.as_ref().map(|until| until());
+        let wait = match (*self.min_retry_interval, backoff) {
+            (Some(interval), Some(backoff)) => Some(interval.max(backoff)),
+            (interval, backoff) => interval.or(backoff),
+        };
+        *self.delaying = wait.map(|wait| delay::sleep(self.cap_to_remaining(wait)));
+        if *self.yield_between_attempts {
+            *self.yielding = Some(yielding::yield_now());
+        }
+        *self.gating = self.gate.as_ref().map(|gate| gate());
+        *self.probing = self.probe.as_ref().map(|probe| probe());
+    }
+
+    /// How long the loop has been running, not counting any time spent paused, including from
+    /// before it was resumed.
+    fn elapsed(&self) -> Duration {
+        running_time(
+            &**self.clock,
+            *self.start,
+            self.pauser.as_ref(),
+            *self.resumed_elapsed,
+        )
+    }
+
+    /// The failure for running out of attempts: `MaxRetries` if the limit came from
+    /// `max_restarts`, otherwise `Exhausted`.
+    fn out_of_attempts(&self, error: E) -> Failure<E> {
+        let restarts = *self.restarts;
+        if *self.restarts_capped {
+            Failure::MaxRetries { error, restarts }
+        } else {
+            Failure::Exhausted { error, restarts }
+        }
+    }
+
+    /// The errors kept by `keep_recent_errors`, and how many were dropped, for a `Failure::Err`
+    /// or `Failure::NonRetryable`.
+    fn recent_errors(&mut self) -> (Vec<E>, usize) {
+        self.recent_errors
+            .as_mut()
+            .map_or_else(|| (Vec::new(), 0), |recent| recent.take())
+    }
+
+    /// Cuts `wait` short so it doesn't run past the timeout or the deadline, or use more than
+    /// `max_backoff_fraction` of the time left until then.
+    fn cap_to_remaining(&self, wait: Duration) -> Duration {
+        let until_timeout = self
+            .timeout
+            .map(|timeout| timeout.saturating_sub(self.elapsed()));
+        let until_deadline = self
+            .deadline
+            .map(|deadline| deadline.remaining_at(self.clock.now()));
+        let remaining = match (until_timeout, until_deadline) {
+            (Some(until_timeout), Some(until_deadline)) => until_timeout.min(until_deadline),
+            (until_timeout, until_deadline) => match until_timeout.or(until_deadline) {
+                Some(remaining) => remaining,
+                None => return wait,
+            },
+        };
+        match *self.max_backoff_fraction {
+            Some(fraction) => wait.min(remaining.mul_f64(fraction)),
+            None => wait.min(remaining),
+        }
+    }
+
+    /// Whether the timeout has expired after `elapsed`, or the deadline has passed.
+    fn timed_out(&self, elapsed: Duration) -> bool {
+        // A zero timeout has always expired, however coarse the clock is.
+        matches!(*self.timeout, Some(timeout) if timeout.is_zero() || elapsed > timeout)
+            || matches!(*self.deadline, Some(deadline) if deadline.has_passed_at(self.clock.now()))
+    }
+
+    /// Resolves to `success`, starting any background retries first.
+    fn succeed(&mut self, mut success: Success<T, Raw>, elapsed: Duration) -> Success<T, Raw> {
+        self.abandon_attempt();
+        success.attempt_durations = std::mem::take(self.attempt_durations);
+        if let Some(background) = self.background.take() {
+            let remaining = self.timeout.map(|timeout| timeout.saturating_sub(elapsed));
+            let clock = &*self.clock;
+            let until_deadline = self
+                .deadline
+                .map(|deadline| deadline.remaining_at(clock.now()));
+            // This loop is done, so its settings can be handed straight on.
+            background(spawn::Carried {
+                remaining: match (remaining, until_deadline) {
+                    (Some(remaining), Some(until_deadline)) => Some(remaining.min(until_deadline)),
+                    (remaining, until_deadline) => remaining.or(until_deadline),
+                },
+                clock: self.clock.clone(),
+                max_attempts: *self.max_attempts,
+                restarts_capped: *self.restarts_capped,
+                max_pending_polls: *self.max_pending_polls,
+                min_retry_interval: *self.min_retry_interval,
+                attempt_timeout: self.attempt_timeout.take().map(Arc::from),
+                backoff: self
+                    .backoff
+                    .take()
+                    .map(|backoff| Arc::new(std::sync::Mutex::new(backoff))),
+            });
+        }
+        if let Some(metrics) = self.metrics.as_ref() {
+            let reason = match success.restarts {
+                0 => CompletionReason::FirstTry,
+                _ => CompletionReason::RetriedThenPassed,
+            };
+            metrics.on_complete(reason, elapsed, success.restarts, self.ctx);
+        }
+        success
+    }
+
+    /// Stops because `until` said to, with the most recent value which passed the test, if one was
+    /// held on to.
+    fn stop_requested(&mut self) -> Outcome<T, E, Raw> {
+        let latest = self
+            .success_rate
+            .as_mut()
+            .and_then(|rate| rate.latest.take());
+        if let Some((value, raw_output)) = latest {
+            let elapsed = self.elapsed();
+            let success = Success {
+                value,
+                duration: elapsed,
+                restarts: *self.restarts,
+                raw_output,
+                attempt_durations: Vec::new(),
+            };
+            return Ok(self.succeed(success, elapsed));
+        }
+        let restarts = *self.restarts;
+        self.fail(Failure::StopRequested { restarts })
+    }
+
+    /// Gives up with `failure`, unless an earlier attempt was accepted provisionally.
+    fn fail(&mut self, failure: Failure<E>) -> Outcome<T, E, Raw> {
+        self.abandon_attempt();
+        let outcome = match self.provisional.take() {
+            Some(mut success) => {
+                success.attempt_durations = std::mem::take(self.attempt_durations);
+                Ok(success)
+            }
+            None => Err(failure),
+        };
+        if let Some(metrics) = self.metrics.as_ref() {
+            let elapsed = self.elapsed();
+            metrics.on_complete(outcome.reason(), elapsed, *self.restarts, self.ctx);
+        }
+        outcome
+    }
+}
+
+/// How long a loop which started at `start` has been running by `clock`, not counting any time
+/// spent paused, plus how long it had already run for before it was resumed. The clock is only
+/// read if the loop has started, so an untimed loop never reads it.
+fn running_time(
+    clock: &dyn Clock,
+    start: Option<Instant>,
+    pauser: Option<&(Pauser, Duration)>,
+    resumed_elapsed: Duration,
+) -> Duration {
+    let elapsed = start.map_or(Duration::ZERO, |start| {
+        clock.now().saturating_duration_since(start)
+    });
+    let elapsed = match pauser {
+        Some((pauser, paused_before_start)) => {
+            elapsed.saturating_sub(pauser.paused_total() - *paused_before_start)
+        }
+        None => elapsed,
+    };
+    elapsed + resumed_elapsed
+}
+
+/// Warns that a Restartable has nothing to stop it retrying forever, once per process. This only
+/// does anything in debug builds, or with the `use_log` feature.
+fn warn_unbounded() {
+    #[cfg(any(feature = "use_log", debug_assertions))]
+    {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        static WARNED: AtomicBool = AtomicBool::new(false);
+        if !WARNED.swap(true, Ordering::Relaxed) {
+            let warning = "a Restartable has no timeout, deadline, attempt limit or cancellation, \
+                so it might retry forever. Call `allow_unbounded` on it if that's intended.";
+            #[cfg(feature = "use_log")]
+            log::warn!("{}", warning);
+            #[cfg(not(feature = "use_log"))]
+            eprintln!("warning: {}", warning);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn restarts_saturate_instead_of_overflowing() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let retrying = Restartable::new(|| std::future::ready(()), None, |()| Err::<(), _>(()));
+        let mut retrying = Box::pin(retrying);
+        *retrying.as_mut().project().restarts = usize::MAX - 2;
+
+        // Every attempt fails, so each poll restarts, right past where the counter would overflow.
+        for _ in 0..5 {
+            assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(retrying.restarts, usize::MAX);
+    }
+
+    #[tokio::test]
+    async fn only_max_restarts_counts_as_hitting_the_retry_cap() {
+        let failure = Restartable::new(|| async { 1 }, None, Err::<(), i32>)
+            .max_restarts(2)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            failure,
+            Failure::MaxRetries {
+                error: 1,
+                restarts: 2
+            }
+        );
+        assert_eq!(failure.reason(), StopReason::MaxRetries);
+        assert_eq!(
+            failure.to_string(),
+            "hit the cap of 2 restarts, last error: 1"
+        );
+        assert_eq!(
+            Failure::try_from(failure.clone().into_stopped()).unwrap(),
+            failure
+        );
+
+        let failure = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 3)
+            .await
+            .unwrap_err();
+        assert_eq!(failure.reason(), StopReason::Exhausted);
+    }
+
+    #[tokio::test]
+    async fn factory_errors_skip_the_attempt_hooks() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Mutex;
+
+        let builds = AtomicUsize::new(0);
+        let factory = || match builds.fetch_add(1, Ordering::SeqCst) {
+            0 => Ok(async { 1 }),
+            _ => Err("no config"),
+        };
+        let inspected = Arc::new(Mutex::new(Vec::new()));
+        let seen = inspected.clone();
+        let failure = try_factory(factory, None, |n: i32| Err::<(), _>(n.to_string()))
+            .unwrap()
+            .inspect(move |result| seen.lock().unwrap().push(result.clone()))
+            .keep_recent_errors(5)
+            .await
+            .unwrap_err();
+        // Only the attempt which actually ran was inspected.
+        assert_eq!(*inspected.lock().unwrap(), [Err("1".to_owned())]);
+        assert_eq!(
+            failure,
+            Failure::FactoryError {
+                error: "no config".to_owned(),
+                restarts: 1
+            }
+        );
+    }
+
+    #[test]
+    fn stopped_combinations_no_failure_has_are_given_back() {
+        let stopped = Stopped::<i32> {
+            reason: StopReason::Exhausted,
+            error: None,
+            restarts: 3,
+            elapsed: None,
+        };
+        let unmatched = Failure::try_from(stopped).unwrap_err();
+        assert_eq!(unmatched.0.reason, StopReason::Exhausted);
+        assert_eq!(unmatched.0.restarts, 3);
+        assert_eq!(
+            unmatched.to_string(),
+            "no Failure stops for Exhausted without an error"
+        );
+
+        let stopped = Stopped {
+            reason: StopReason::Cancelled,
+            error: Some(1),
+            restarts: 0,
+            elapsed: None,
+        };
+        assert_eq!(Failure::try_from(stopped).unwrap_err().0.error, Some(1));
+    }
+
+    #[tokio::test]
+    async fn retry_n_never_reads_the_clock() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Clone, Default)]
+        struct CountingClock(Arc<AtomicUsize>);
+
+        impl Clock for CountingClock {
+            fn now(&self) -> Instant {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Instant::now()
+            }
+        }
+
+        let clock = CountingClock::default();
+        let outcome = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 5)
+            .clock(clock.clone())
+            .await;
+        assert!(matches!(
+            outcome,
+            Err(Failure::Exhausted { restarts: 4, .. })
+        ));
+        assert_eq!(clock.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn nested_loops_stop_at_their_shared_deadline() {
+        let deadline = Deadline::after(Duration::from_millis(50));
+        let start = Instant::now();
+
+        // The inner loop's attempts never finish, so only the deadline can stop it before its
+        // minute-long timeout. They wake the task every few milliseconds, so it's checked.
+        let attempt = || async {
+            loop {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        };
+        let inner = || {
+            Restartable::new(
+                attempt,
+                Some(Duration::from_secs(60)),
+                |()| Ok::<(), ()>(()),
+            )
+            .deadline(deadline)
+        };
+        let outcome = Restartable::new(inner, None, |inner_outcome| inner_outcome)
+            .deadline(deadline)
+            .await;
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        match outcome {
+            Err(Failure::Err {
+                error:
+                    Failure::Timeout {
+                        restarts: 0,
+                        scope: TimeoutScope::Overall,
+                        ..
+                    },
+                restarts: 0,
+                ..
+            }) => {}
+            other => panic!(
+                "expected the inner loop to pass the deadline, got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_n_bounds_attempts_abandoned_for_pending_too_long() {
+        let attempts = std::sync::atomic::AtomicUsize::new(0);
+        let factory = || {
+            attempts.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::future::pending::<()>()
+        };
+        let outcome = Restartable::retry_n(factory, |()| Ok::<(), ()>(()), 3)
+            .max_pending_polls(2)
+            .await;
+        match outcome {
+            Err(Failure::Timeout {
+                restarts: 2,
+                scope: TimeoutScope::Attempt,
+                ..
+            }) => {}
+            other => panic!("expected the third attempt to time out, got {:?}", other),
+        }
+        assert_eq!(attempts.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn zero_timeout_fails_after_one_attempt() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = || {
+            attempts.set(attempts.get() + 1);
+            std::future::ready(())
+        };
+        let retrying = Restartable::new(factory, Some(Duration::ZERO), |()| Err::<(), _>(()));
+        let mut retrying = Box::pin(retrying);
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(Failure::Err { restarts: 0, .. })) => {}
+            other => panic!("expected the first attempt's error, got {:?}", other),
+        }
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn zero_timeout_passes_on_first_attempt() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let retrying =
+            Restartable::new(|| std::future::ready(1), Some(Duration::ZERO), Ok::<_, ()>);
+        match Box::pin(retrying).as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(success)) => assert_eq!((success.value, success.restarts), (1, 0)),
+            other => panic!("expected a success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn zero_timeout_gives_up_on_pending_attempt() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let retrying = Restartable::new(
+            std::future::pending::<()>,
+            Some(Duration::ZERO),
+            Ok::<_, ()>,
+        );
+        match Box::pin(retrying).as_mut().poll(&mut cx) {
+            Poll::Ready(Err(Failure::Timeout { restarts: 0, .. })) => {}
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn until_err_restarts_on_ok_and_resolves_on_err() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = || {
+            attempts.set(attempts.get() + 1);
+            std::future::ready(attempts.get())
+        };
+        let done_at_three = |n| if n < 3 { Ok(n) } else { Err(n) };
+        let mut retrying = Box::pin(Restartable::until_err(factory, None, done_at_three));
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(success)) => assert_eq!((success.value, success.restarts), (3, 2)),
+            other => panic!("expected a success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn until_err_gives_up_with_the_last_ok_value() {
+        let retrying = Restartable::until_err(|| std::future::ready(7), None, Ok::<_, ()>);
+        let mut retrying = Box::pin(retrying);
+        *retrying.as_mut().project().max_attempts = Some(2);
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(Failure::Exhausted {
+                error: 7,
+                restarts: 1,
+            })) => {}
+            other => panic!("expected exhaustion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn only_loops_without_a_stop_condition_are_unbounded() {
+        let factory = || std::future::ready(());
+        assert!(Restartable::new(factory, None, Ok::<_, ()>).is_unbounded());
+        let bounded = [
+            Restartable::new(factory, Some(Duration::from_secs(1)), Ok::<_, ()>),
+            Restartable::new(factory, None, Ok::<_, ()>).deadline(Deadline::after(Duration::ZERO)),
+            Restartable::retry_n(factory, Ok::<_, ()>, 3),
+            Restartable::new(factory, None, Ok::<_, ()>).allow_unbounded(),
+        ];
+        for retrying in bounded {
+            assert!(!retrying.is_unbounded());
+        }
+    }
+
+    #[test]
+    fn then_retry_shares_the_time_budget() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = |_: &u8| {
+            attempts.set(attempts.get() + 1);
+            std::future::ready(())
+        };
+        // The first loop uses up the whole (zero) budget, so the second loop only gets one try.
+        let retrying =
+            Restartable::new(|| std::future::ready(1), Some(Duration::ZERO), Ok::<_, ()>)
+                .then_retry(factory, |()| Err::<(), _>(()));
+        match Box::pin(retrying).as_mut().poll(&mut cx) {
+            Poll::Ready(Err(Failure::Err { restarts: 0, .. })) => {}
+            other => panic!("expected the second loop's error, got {:?}", other),
+        }
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn retry_batch_caps_concurrency_and_keeps_input_order() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let in_flight = std::cell::Cell::new(0);
+        let most_in_flight = std::cell::Cell::new(0);
+        // Each attempt takes two polls. Odd inputs always fail.
+        let factory = |input: &usize| {
+            let input = *input;
+            let mut polled = false;
+            let (in_flight, most_in_flight) = (&in_flight, &most_in_flight);
+            std::future::poll_fn(move |_| {
+                if !polled {
+                    polled = true;
+                    in_flight.set(in_flight.get() + 1);
+                    most_in_flight.set(most_in_flight.get().max(in_flight.get()));
+                    return Poll::Pending;
+                }
+                in_flight.set(in_flight.get() - 1);
+                Poll::Ready(if input.is_multiple_of(2) {
+                    Ok(input)
+                } else {
+                    Err(input)
+                })
+            })
+        };
+        let policy = RetryPolicy::builder().max_attempts(2).build();
+        let mut batch = Box::pin(retry_batch(vec![0, 1, 2, 3, 4], factory, |r| r, policy, 2));
+        let batch = loop {
+            if let Poll::Ready(batch) = batch.as_mut().poll(&mut cx) {
+                break batch;
+            }
+        };
+        assert_eq!(most_in_flight.get(), 2);
+        assert_eq!(batch.successes().count(), 3);
+        assert_eq!(batch.failures().count(), 2);
+        let values: Vec<_> = batch
+            .outcomes()
+            .iter()
+            .map(|outcome| match outcome {
+                Ok(success) => success.value,
+                Err(Failure::Exhausted { error, .. }) => *error,
+                other => panic!("expected exhaustion, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(values, [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn success_rate_met_on_a_failure_resolves_with_the_latest_pass() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = || {
+            attempts.set(attempts.get() + 1);
+            std::future::ready(attempts.get())
+        };
+        // Attempts 1 and 2 pass, then 3 fails, which fills the window at two thirds.
+        let pass_twice = |n| if n < 3 { Ok(n) } else { Err(n) };
+        let retrying = Restartable::new(factory, None, pass_twice).require_success_rate(3, 0.6);
+        let mut retrying = Box::pin(retrying);
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(success)) => assert_eq!((success.value, success.restarts), (2, 2)),
+            other => panic!("expected a success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_nan_success_rate_needs_every_attempt_to_pass() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let retrying = Restartable::new(|| std::future::ready(1), None, Ok::<_, ()>)
+            .require_success_rate(2, f64::NAN);
+        let mut retrying = Box::pin(retrying);
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(success)) => assert_eq!(success.restarts, 1),
+            other => panic!("expected a success, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn success_rate_unmet_when_attempts_run_out_after_a_pass() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let retrying = Restartable::retry_n(|| std::future::ready(1), Ok::<_, ()>, 2)
+            .require_success_rate(3, 1.0);
+        let mut retrying = Box::pin(retrying);
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(failure)) => {
+                assert_eq!(failure, Failure::SuccessRateUnmet { restarts: 1 });
+                assert_eq!(failure.reason(), StopReason::SuccessRateUnmet);
+                assert!(failure.is_retryable_later());
+            }
+            other => panic!("expected an unmet success rate, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn until_stops_once_the_predicate_flips() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let checks = Arc::new(AtomicUsize::new(0));
+        let counter = checks.clone();
+        let retrying = Restartable::new(|| std::future::ready(()), None, |()| Err::<(), _>(()))
+            .until(move || std::future::ready(counter.fetch_add(1, Ordering::SeqCst) == 2));
+        let mut retrying = Box::pin(retrying);
+        for _ in 0..3 {
+            assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        }
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Err(Failure::StopRequested { restarts: 3 })) => {}
+            other => panic!("expected a requested stop, got {:?}", other),
+        }
+        assert_eq!(checks.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn until_stops_with_the_latest_provisional_value() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = || {
+            attempts.set(attempts.get() + 1);
+            std::future::ready(attempts.get())
+        };
+        let checks = Arc::new(AtomicUsize::new(0));
+        let counter = checks.clone();
+        let retrying = Restartable::with_resolution(factory, None, |n| {
+            Resolution::<_, ()>::AcceptButContinue(n)
+        })
+        .until(move || std::future::ready(counter.fetch_add(1, Ordering::SeqCst) == 1));
+        let mut retrying = Box::pin(retrying);
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        assert!(retrying.as_mut().poll(&mut cx).is_pending());
+        match retrying.as_mut().poll(&mut cx) {
+            Poll::Ready(Ok(success)) => assert_eq!(success.value, 2),
+            other => panic!("expected the provisional value, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn success_records_every_attempts_duration() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let attempts = std::cell::Cell::new(0);
+        let factory = || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt == 2 {
+                    std::thread::sleep(Duration::from_millis(20));
+                }
+                attempt
+            }
+        };
+        let retrying = Restartable::new(factory, None, |n| if n == 3 { Ok(n) } else { Err(n) });
+        let mut retrying = Box::pin(retrying);
+        let success = loop {
+            if let Poll::Ready(outcome) = retrying.as_mut().poll(&mut cx) {
+                break outcome.unwrap();
+            }
+        };
+        assert_eq!(success.attempt_durations.len(), 3);
+        assert!(success.attempt_durations[1] >= Duration::from_millis(20));
+    }
+}
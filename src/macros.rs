@@ -0,0 +1,52 @@
+/// Retries an `async` block until its output passes a test, or the timeout expires.
+///
+/// Expands to a [`Restartable`](crate::Restartable) whose factory re-runs the block, so each
+/// attempt gets a fresh future. Takes the block, then the timeout and test, in the same order as
+/// [`Restartable::new`](crate::Restartable::new).
+///
+/// A plain `async` block borrows the variables it uses, so they must outlive the `Restartable`:
+///
+/// ```
+/// use restartables::retry;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let words = vec!["a", "bb", "ccc"];
+/// let retrying = retry!(
+///     async { words[rand::random::<usize>() % words.len()] },
+///     Some(Duration::from_secs(1)),
+///     |word: &str| if word.len() == 3 { Ok(word) } else { Err(word) }
+/// );
+/// assert_eq!(retrying.await.unwrap().value, "ccc");
+/// # }
+/// ```
+///
+/// An `async move` block copies its captures into every attempt, so it can only capture `Copy`
+/// values, like references or integers. To use owned data, take a reference to it first:
+///
+/// ```
+/// use restartables::retry;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let target = 3;
+/// let words = &vec!["a", "bb", "ccc"];
+/// let retrying = retry!(
+///     async move { words[rand::random::<usize>() % words.len()].len() },
+///     Some(Duration::from_secs(1)),
+///     |len| if len == target { Ok(len) } else { Err(len) }
+/// );
+/// assert_eq!(retrying.await.unwrap().value, 3);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! retry {
+    (async move $body:block, $timeout:expr, $test:expr $(,)?) => {
+        $crate::Restartable::new(move || async move $body, $timeout, $test)
+    };
+    (async $body:block, $timeout:expr, $test:expr $(,)?) => {
+        $crate::Restartable::new(|| async $body, $timeout, $test)
+    };
+}
@@ -0,0 +1,257 @@
+use crate::CompletionReason;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Receives metrics from a Restartable while it runs, e.g. to export them to StatsD or Prometheus.
+///
+/// Set one with [`Restartable::metrics`](crate::Restartable::metrics). Every method does nothing
+/// by default, so implementors only need to override the events they care about. A Restartable
+/// without a sink doesn't build any of these events.
+///
+/// Every event also gets the context from
+/// [`Restartable::with_context`](crate::Restartable::with_context), which is `()` by default.
+pub trait MetricsSink<T, E, Ctx = ()> {
+    /// Called when an attempt is polled for the first time. `attempt` is 0 for the first attempt,
+    /// 1 for the first restart, and so on.
+    fn on_attempt_start(&self, attempt: usize, ctx: &Ctx) {
+        let _ = (attempt, ctx);
+    }
+
+    /// Called when an attempt resolves, with the test's result and how long the attempt took.
+    /// Attempts which are abandoned before they resolve end with
+    /// [`on_attempt_abandoned`](MetricsSink::on_attempt_abandoned) instead.
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, ctx: &Ctx) {
+        let _ = (result, duration, ctx);
+    }
+
+    /// Called when an attempt is dropped before it resolves, e.g. by a per-attempt timeout, or
+    /// because the whole loop timed out or was cancelled, with how long the attempt had run for.
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, ctx: &Ctx) {
+        let _ = (attempt, duration, ctx);
+    }
+
+    /// Called once, when the Restartable resolves, with how it finished, how long it took overall,
+    /// and how many restarts it made.
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        ctx: &Ctx,
+    ) {
+        let _ = (reason, duration, restarts, ctx);
+    }
+}
+
+impl<T, E, Ctx, S> MetricsSink<T, E, Ctx> for Arc<S>
+where
+    S: MetricsSink<T, E, Ctx> + ?Sized,
+{
+    fn on_attempt_start(&self, attempt: usize, ctx: &Ctx) {
+        (**self).on_attempt_start(attempt, ctx)
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, ctx: &Ctx) {
+        (**self).on_attempt_end(result, duration, ctx)
+    }
+
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, ctx: &Ctx) {
+        (**self).on_attempt_abandoned(attempt, duration, ctx)
+    }
+
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        ctx: &Ctx,
+    ) {
+        (**self).on_complete(reason, duration, restarts, ctx)
+    }
+}
+
+/// Adapts a sink which doesn't use a context, for a Restartable which has one.
+pub(crate) struct IgnoreContext<S: ?Sized>(pub(crate) Box<S>);
+
+impl<T, E, Ctx, S> MetricsSink<T, E, Ctx> for IgnoreContext<S>
+where
+    S: MetricsSink<T, E> + ?Sized,
+{
+    fn on_attempt_start(&self, attempt: usize, _ctx: &Ctx) {
+        self.0.on_attempt_start(attempt, &())
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, _ctx: &Ctx) {
+        self.0.on_attempt_end(result, duration, &())
+    }
+
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, _ctx: &Ctx) {
+        self.0.on_attempt_abandoned(attempt, duration, &())
+    }
+
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        _ctx: &Ctx,
+    ) {
+        self.0.on_complete(reason, duration, restarts, &())
+    }
+}
+
+/// A [`MetricsSink`] which ignores every event.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NoopSink;
+
+impl<T, E, Ctx> MetricsSink<T, E, Ctx> for NoopSink {}
+
+/// A [`MetricsSink`] which prints every event to stdout, for debugging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct StdoutSink;
+
+impl<T, E, Ctx> MetricsSink<T, E, Ctx> for StdoutSink {
+    fn on_attempt_start(&self, attempt: usize, _ctx: &Ctx) {
+        println!("attempt {} started", attempt);
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, _ctx: &Ctx) {
+        let verdict = if result.is_ok() { "passed" } else { "failed" };
+        println!("attempt {} after {:?}", verdict, duration);
+    }
+
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, _ctx: &Ctx) {
+        println!("attempt {} abandoned after {:?}", attempt, duration);
+    }
+
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        _ctx: &Ctx,
+    ) {
+        println!(
+            "completed with {:?} after {:?} and {} restarts",
+            reason, duration, restarts
+        );
+    }
+}
+
+/// One attempt's entry in an [`AttemptLog`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttemptRecord {
+    /// 0 for the first attempt, 1 for the first restart, and so on
+    pub index: usize,
+    /// When the attempt was first polled, by the system clock, for lining records up with logs
+    pub started_at: Instant,
+    /// How long the attempt ran for, from the Restartable's own clock
+    pub duration: Duration,
+    /// `Ok` if the attempt passed the test, or the test's error, as a string. Attempts which were
+    /// abandoned before they resolved, e.g. by a per-attempt timeout, have an error saying so.
+    pub result: Result<(), String>,
+}
+
+/// A [`MetricsSink`] which keeps a record of every attempt, for post-mortems of flaky behaviour.
+///
+/// Clones share the same records, so keep a clone, pass another to
+/// [`Restartable::metrics`](crate::Restartable::metrics), and read the records once the
+/// Restartable resolves, whether it succeeded or failed. Only the most recent `capacity` records
+/// are kept, so a long-running loop doesn't use unbounded memory.
+///
+/// ```
+/// use restartables::{AttemptLog, Restartable};
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let log = AttemptLog::with_capacity(2);
+/// let attempts = AtomicUsize::new(0);
+/// let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+/// let retrying = Restartable::retry_n(factory, |n| Err::<(), _>(format!("{} failed", n)), 3)
+///     .metrics(log.clone());
+/// assert!(retrying.await.is_err());
+///
+/// let records = log.records();
+/// assert_eq!(records.len(), 2);
+/// assert_eq!(records[0].index, 1);
+/// assert_eq!(records[1].result, Err("2 failed".to_owned()));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AttemptLog {
+    inner: Arc<Mutex<LogState>>,
+}
+
+#[derive(Debug)]
+struct LogState {
+    capacity: usize,
+    records: VecDeque<AttemptRecord>,
+    /// The current attempt's index and start time, until it ends.
+    current: Option<(usize, Instant)>,
+}
+
+impl AttemptLog {
+    /// Keeps records of the most recent `capacity` attempts.
+    pub fn with_capacity(capacity: usize) -> Self {
+        AttemptLog {
+            inner: Arc::new(Mutex::new(LogState {
+                capacity,
+                records: VecDeque::new(),
+                current: None,
+            })),
+        }
+    }
+
+    /// The records so far, oldest first.
+    pub fn records(&self) -> Vec<AttemptRecord> {
+        self.lock().records.iter().cloned().collect()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LogState> {
+        // A panic while holding the lock can't leave the records half-written.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl LogState {
+    /// Ends the current attempt, if there is one.
+    fn end(&mut self, duration: Duration, result: Result<(), String>) {
+        if let Some((index, started_at)) = self.current.take() {
+            if self.capacity == 0 {
+                return;
+            }
+            if self.records.len() == self.capacity {
+                self.records.pop_front();
+            }
+            self.records.push_back(AttemptRecord {
+                index,
+                started_at,
+                duration,
+                result,
+            });
+        }
+    }
+}
+
+impl<T, E: fmt::Display, Ctx> MetricsSink<T, E, Ctx> for AttemptLog {
+    fn on_attempt_start(&self, attempt: usize, _ctx: &Ctx) {
+        self.lock().current = Some((attempt, Instant::now()));
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, _ctx: &Ctx) {
+        let result = match result {
+            Ok(_) => Ok(()),
+            Err(error) => Err(error.to_string()),
+        };
+        self.lock().end(duration, result);
+    }
+
+    fn on_attempt_abandoned(&self, _attempt: usize, duration: Duration, _ctx: &Ctx) {
+        self.lock().end(duration, Err(ABANDONED.to_owned()));
+    }
+}
+
+const ABANDONED: &str = "abandoned before resolving";
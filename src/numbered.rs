@@ -0,0 +1,83 @@
+use crate::{Numbered, Restartable};
+use std::future::Future;
+use std::time::Duration;
+
+/// Like [`Restartable::new`], but the test also gets how many times the future has been
+/// restarted so far, starting at 0 for the first attempt.
+///
+/// This is for tests which adapt as the loop goes on, e.g. relaxing a threshold on later
+/// attempts, rather than holding out for a perfect result until the timeout.
+///
+/// ```
+/// use restartables::numbered_test;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Every measured latency is 180ms.
+/// let measurements = AtomicU32::new(0);
+/// let measure = || async {
+///     measurements.fetch_add(1, Ordering::SeqCst);
+///     180
+/// };
+/// // Ask for under 100ms, then settle for 50ms more on every restart.
+/// let fast_enough = |latency_ms: u32, restarts: usize| {
+///     let limit = 100 + 50 * restarts as u32;
+///     if latency_ms < limit { Ok(latency_ms) } else { Err(latency_ms) }
+/// };
+/// let success = numbered_test(measure, Some(Duration::from_secs(1)), fast_enough)
+///     .await
+///     .unwrap();
+/// assert_eq!(success.restarts, 2);
+/// assert_eq!(measurements.load(Ordering::SeqCst), 3);
+/// # }
+/// ```
+pub fn numbered_test<Fut, Factory, Test, T, E>(
+    factory: Factory,
+    timeout: Option<Duration>,
+    test: Test,
+) -> Restartable<Fut, Numbered<Test>, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output, usize) -> Result<T, E>,
+{
+    Restartable::from_parts(factory, timeout, Numbered(test)).with_global_defaults()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[tokio::test]
+    async fn abandoned_attempts_still_count() {
+        let seen = Mutex::new(Vec::new());
+        let attempts = AtomicUsize::new(0);
+        // The first attempt never resolves, so it's abandoned after too many pending polls.
+        let factory = || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    std::future::pending::<()>().await;
+                }
+            }
+        };
+        let test = |(), restarts| {
+            seen.lock().unwrap().push(restarts);
+            if restarts == 2 {
+                Ok(())
+            } else {
+                Err(())
+            }
+        };
+        let success = numbered_test(factory, None, test)
+            .max_pending_polls(3)
+            .await
+            .unwrap();
+        assert_eq!(success.restarts, 2);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Resolve, Restartable, Success};
+use std::future::Future;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::{Duration, Instant};
+
+/// What a single attempt from [`Restartable::now_or_never`] produced.
+#[derive(Debug)]
+pub enum SingleShot<T, E, Raw = ()> {
+    /// The inner future resolved straight away, and its output passed the test.
+    Passed(Success<T, Raw>),
+    /// The inner future resolved straight away, but its output failed the test.
+    Failed(E),
+    /// The inner future didn't resolve on its first poll.
+    Pending,
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Polls the first attempt exactly once, without retrying, and runs the test on its output.
+    ///
+    /// This is for "try once, then fall back" code, and for testing a factory's first attempt on
+    /// its own. The timeouts, cancellation, background retries, [`metrics`](Restartable::metrics),
+    /// [`probe_with`](Restartable::probe_with) and [`confirm_with`](Restartable::confirm_with) are
+    /// all ignored. The attempt is polled with a
+    /// waker which does nothing, and dropped if it's still pending.
+    ///
+    /// ```
+    /// use restartables::{Restartable, SingleShot};
+    /// use std::time::Duration;
+    ///
+    /// let even = |n: u8| if n % 2 == 0 { Ok(n) } else { Err(n) };
+    /// let timeout = Some(Duration::from_secs(1));
+    ///
+    /// match Restartable::new(|| async { 4 }, timeout, even).now_or_never() {
+    ///     SingleShot::Passed(success) => assert_eq!(success.value, 4),
+    ///     other => panic!("expected a pass, got {:?}", other),
+    /// }
+    /// match Restartable::new(|| async { 3 }, timeout, even).now_or_never() {
+    ///     SingleShot::Failed(n) => assert_eq!(n, 3),
+    ///     other => panic!("expected a failure, got {:?}", other),
+    /// }
+    /// let never = || std::future::pending::<u8>();
+    /// assert!(matches!(
+    ///     Restartable::new(never, timeout, even).now_or_never(),
+    ///     SingleShot::Pending
+    /// ));
+    /// ```
+    pub fn now_or_never(self) -> SingleShot<T, E, Raw> {
+        let start = if self.timed {
+            Some(Instant::now())
+        } else {
+            None
+        };
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let output = match Box::pin(self.future).as_mut().poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return SingleShot::Pending,
+        };
+        let raw_output = (self.keep_raw)(&output);
+        let (result, _) = self.test.resolve(output).split();
+        if let Some(inspect) = self.inspect.as_ref() {
+            inspect(&result, &self.ctx);
+        }
+        match result {
+            Ok(value) => {
+                let duration = start.map_or(Duration::ZERO, |start| start.elapsed());
+                SingleShot::Passed(Success {
+                    value,
+                    duration,
+                    restarts: 0,
+                    raw_output,
+                    attempt_durations: vec![duration],
+                })
+            }
+            Err(error) => SingleShot::Failed(error),
+        }
+    }
+}
+
+/// A waker for polling something once, when nothing needs to be woken.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
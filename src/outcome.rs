@@ -1,29 +1,644 @@
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
 /// Value returned from a successful test, along with metrics.
 ///
 /// If the future eventually resolves a value that passes the test, it returns it, along with some
 /// metrics. This struct combines the value returned, along with how long/how many restarts it took
 /// to get that value.
-#[derive(Debug)]
-pub struct Success<T> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Success<T, Raw = ()> {
     /// The success value returned by the test
     pub value: T,
     /// How much time elapsed while waiting for the future to successfully resolve
     pub duration: std::time::Duration,
-    /// How many times the future needed to be restarted before it successfully resolved
+    /// How many times the future needed to be restarted before it successfully resolved. This
+    /// saturates at `usize::MAX` instead of overflowing.
     pub restarts: usize,
+    /// The inner future's output from the winning attempt, before the test mapped it. Only set if
+    /// [`Restartable::retain_raw_output`](crate::Restartable::retain_raw_output) was used.
+    pub raw_output: Option<Raw>,
+    /// How long each attempt took, from its first poll until it resolved, in the order they were
+    /// made. For a plain loop, the last is the attempt which passed. Attempts abandoned by a
+    /// per-attempt timeout or [`max_pending_polls`](crate::Restartable::max_pending_polls) never
+    /// resolved, so they're left out. Like `duration`, these are zero if the loop isn't timed.
+    pub attempt_durations: Vec<std::time::Duration>,
+}
+
+impl<T, Raw> Success<T, Raw> {
+    /// How many attempts were made, including the one which passed. This is `restarts + 1`.
+    pub fn attempts(&self) -> usize {
+        self.restarts.saturating_add(1)
+    }
+
+    /// Compares two successes by how long they took, for use with e.g. `sort_by`.
+    ///
+    /// ```
+    /// use restartables::Success;
+    /// use std::time::Duration;
+    ///
+    /// let success = |value, millis, restarts| Success {
+    ///     value,
+    ///     duration: Duration::from_millis(millis),
+    ///     restarts,
+    ///     raw_output: None::<()>,
+    ///     attempt_durations: vec![Duration::from_millis(millis)],
+    /// };
+    /// let mut successes = vec![success("slow", 30, 1), success("fast", 10, 2)];
+    /// successes.sort_by(Success::cmp_by_duration);
+    /// assert_eq!(successes[0].value, "fast");
+    /// successes.sort_by(Success::cmp_by_restarts);
+    /// assert_eq!(successes[0].value, "slow");
+    /// ```
+    pub fn cmp_by_duration(&self, other: &Self) -> std::cmp::Ordering {
+        self.duration.cmp(&other.duration)
+    }
+
+    /// Compares two successes by how many restarts they took, for use with e.g. `sort_by`.
+    pub fn cmp_by_restarts(&self, other: &Self) -> std::cmp::Ordering {
+        self.restarts.cmp(&other.restarts)
+    }
+}
+
+/// What a Restartable resolves to: a [`Success`] or a [`Failure`].
+pub type Outcome<T, E, Raw = ()> = Result<Success<T, Raw>, Failure<E>>;
+
+/// How a Restartable finished, whether it succeeded or failed.
+///
+/// This gives metrics pipelines a single label to emit for every outcome. Get it from an
+/// [`Outcome`] with [`OutcomeReason::reason`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionReason {
+    /// The first attempt passed the test
+    FirstTry,
+    /// At least one attempt was restarted before one passed the test
+    RetriedThenPassed,
+    /// The timeout expired
+    TimedOut,
+    /// Every allowed attempt failed the test
+    Exhausted,
+    /// Every restart allowed by [`Restartable::max_restarts`](crate::Restartable::max_restarts)
+    /// failed the test
+    MaxRetries,
+    /// The Restartable was cancelled
+    Cancelled,
+    /// The predicate from [`Restartable::until`](crate::Restartable::until) said to stop
+    StopRequested,
+    /// The factory from [`try_factory`](crate::try_factory) couldn't build an attempt
+    FactoryError,
+    /// The test failed with an error which isn't worth retrying
+    NonRetryable,
+    /// The attempts ran out before enough of them passed, under
+    /// [`Restartable::require_success_rate`](crate::Restartable::require_success_rate)
+    SuccessRateUnmet,
+}
+
+impl From<StopReason> for CompletionReason {
+    fn from(reason: StopReason) -> Self {
+        match reason {
+            StopReason::Timeout => CompletionReason::TimedOut,
+            StopReason::Exhausted => CompletionReason::Exhausted,
+            StopReason::MaxRetries => CompletionReason::MaxRetries,
+            StopReason::Cancelled => CompletionReason::Cancelled,
+            StopReason::StopRequested => CompletionReason::StopRequested,
+            StopReason::FactoryError => CompletionReason::FactoryError,
+            StopReason::NonRetryable => CompletionReason::NonRetryable,
+            StopReason::SuccessRateUnmet => CompletionReason::SuccessRateUnmet,
+        }
+    }
+}
+
+/// Adds [`reason`](OutcomeReason::reason) to [`Outcome`].
+pub trait OutcomeReason {
+    /// Categorises how the Restartable finished.
+    ///
+    /// ```
+    /// use restartables::{CompletionReason, OutcomeReason, Restartable};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let outcome = Restartable::retry_n(|| async { 3 }, |n| if n % 2 == 0 { Ok(n) } else { Err(n) }, 2)
+    ///     .await;
+    /// assert_eq!(outcome.reason(), CompletionReason::Exhausted);
+    ///
+    /// let outcome = Restartable::retry_n(|| async { 4 }, |n| if n % 2 == 0 { Ok(n) } else { Err(n) }, 2)
+    ///     .await;
+    /// assert_eq!(outcome.reason(), CompletionReason::FirstTry);
+    /// # }
+    /// ```
+    fn reason(&self) -> CompletionReason;
+}
+
+impl<T, E, Raw> OutcomeReason for Outcome<T, E, Raw> {
+    fn reason(&self) -> CompletionReason {
+        match self {
+            Ok(success) if success.restarts == 0 => CompletionReason::FirstTry,
+            Ok(_) => CompletionReason::RetriedThenPassed,
+            Err(failure) => failure.reason().into(),
+        }
+    }
 }
 
 /// Different ways a Restartable can fail
-#[derive(Debug)]
+///
+/// New ways to fail may be added without a major version bump, so matches on this need a
+/// wildcard arm. [`Failure::reason`] and [`Failure::restarts`] cover every variant.
+///
+/// A failure only records what stopped the loop, not where its time went. For a breakdown of the
+/// time spent in attempts and between them, run the loop with
+/// [`Restartable::with_report`](crate::Restartable::with_report), whose
+/// [`RetryReport`](crate::RetryReport) covers failures just like successes.
+///
+/// Failures, like successes, can be cloned and compared whenever their values can, which makes
+/// for simple assertions in tests:
+///
+/// ```
+/// use restartables::{Failure, Restartable};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let outcome = Restartable::retry_n(|| async { 3 }, |n: u8| Err::<u8, _>(n), 2).await;
+/// assert_eq!(outcome, Err(Failure::Exhausted { error: 3, restarts: 1 }));
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Failure<E> {
     /// Returned if the inner future never resolved before the timeout
-    Timeout,
-    /// Returned if the inner future fails the test and then times out. Returns the last error
-    /// from the test, and how many times the future was restarted.
+    Timeout {
+        /// How many times the future was restarted before the timeout expired
+        restarts: usize,
+        /// Which timeout expired
+        scope: TimeoutScope,
+        /// How long the Restartable ran before giving up, like [`Success::duration`]
+        elapsed: Duration,
+    },
+    /// Returned if the inner future fails the test and then times out, or keeps failing with
+    /// the same error under
+    /// [`Restartable::give_up_on_repeated_error`](crate::Restartable::give_up_on_repeated_error).
+    /// Returns the last error from the test, and how many times the future was restarted.
     Err {
         /// The failure value returne by the test
         error: E,
         /// How many times the future was restarted before the timeout expired
         restarts: usize,
+        /// How long the Restartable ran before giving up, like [`Success::duration`]
+        elapsed: Duration,
+        /// The last few errors from the test, oldest first, if
+        /// [`Restartable::keep_recent_errors`](crate::Restartable::keep_recent_errors) was used.
+        /// Otherwise this is empty.
+        recent_errors: Vec<E>,
+        /// How many older errors were dropped from `recent_errors` to keep it within its limit.
+        dropped_errors: usize,
+    },
+    /// Returned if the inner future failed the test on every attempt allowed by
+    /// [`Restartable::retry_n`](crate::Restartable::retry_n) or a
+    /// [`RetryPolicy`](crate::RetryPolicy)'s `max_attempts`, or until a
+    /// [`Backoff`](crate::Backoff) said to stop, i.e. it ran out of attempts rather than time.
+    /// Returns the last error from the test.
+    Exhausted {
+        /// The failure value returned by the last attempt's test
+        error: E,
+        /// How many times the future was restarted before the attempts ran out
+        restarts: usize,
+    },
+    /// Returned if the inner future failed the test on every restart allowed by
+    /// [`Restartable::max_restarts`](crate::Restartable::max_restarts), i.e. it hit the retry cap
+    /// rather than the clock. Returns the last error from the test.
+    MaxRetries {
+        /// The failure value returned by the last attempt's test
+        error: E,
+        /// How many times the future was restarted, which is the cap
+        restarts: usize,
+    },
+    /// Returned if the Restartable was cancelled before the inner future passed the test.
+    Cancelled {
+        /// How many times the future was restarted before it was cancelled
+        restarts: usize,
+    },
+    /// Returned if the predicate from [`Restartable::until`](crate::Restartable::until) said to
+    /// stop before any attempt passed the test.
+    StopRequested {
+        /// How many times the future was restarted before the stop was requested
+        restarts: usize,
+    },
+    /// Returned if the factory from [`try_factory`](crate::try_factory) couldn't build the next
+    /// attempt's future. Returns the factory's error, converted into the test's error type.
+    FactoryError {
+        /// The error returned by the factory
+        error: E,
+        /// How many times the future was restarted before the factory failed
+        restarts: usize,
     },
+    /// Returned if the test failed with an error which
+    /// [`Restartable::retry_if`](crate::Restartable::retry_if) said isn't worth retrying. This
+    /// takes priority over running out of attempts, so it's returned even for the last one.
+    NonRetryable {
+        /// The failure value returned by the test
+        error: E,
+        /// How many times the future was restarted before the error
+        restarts: usize,
+        /// How long the Restartable ran before giving up, like [`Success::duration`]
+        elapsed: Duration,
+        /// The last few errors from the test, oldest first, like [`Failure::Err`]'s
+        recent_errors: Vec<E>,
+        /// How many older errors were dropped from `recent_errors`, like [`Failure::Err`]'s
+        dropped_errors: usize,
+    },
+    /// Returned if the attempts ran out before enough of them passed the test, under
+    /// [`Restartable::require_success_rate`](crate::Restartable::require_success_rate), and the
+    /// last attempt passed, so there's no error to return. If the last attempt failed, the loop
+    /// returns [`Failure::Exhausted`] or [`Failure::MaxRetries`] with its error instead.
+    SuccessRateUnmet {
+        /// How many times the future was restarted before the attempts ran out
+        restarts: usize,
+    },
+}
+
+impl<E> Failure<E> {
+    /// Which stop condition ended the Restartable.
+    pub fn reason(&self) -> StopReason {
+        match self {
+            Failure::Timeout { .. } | Failure::Err { .. } => StopReason::Timeout,
+            Failure::Exhausted { .. } => StopReason::Exhausted,
+            Failure::MaxRetries { .. } => StopReason::MaxRetries,
+            Failure::Cancelled { .. } => StopReason::Cancelled,
+            Failure::StopRequested { .. } => StopReason::StopRequested,
+            Failure::FactoryError { .. } => StopReason::FactoryError,
+            Failure::NonRetryable { .. } => StopReason::NonRetryable,
+            Failure::SuccessRateUnmet { .. } => StopReason::SuccessRateUnmet,
+        }
+    }
+
+    /// How many times the future was restarted before the Restartable gave up.
+    pub fn restarts(&self) -> usize {
+        match self {
+            Failure::Timeout { restarts, .. }
+            | Failure::Err { restarts, .. }
+            | Failure::Exhausted { restarts, .. }
+            | Failure::MaxRetries { restarts, .. }
+            | Failure::Cancelled { restarts }
+            | Failure::StopRequested { restarts }
+            | Failure::FactoryError { restarts, .. }
+            | Failure::NonRetryable { restarts, .. }
+            | Failure::SuccessRateUnmet { restarts } => *restarts,
+        }
+    }
+
+    /// How long the Restartable ran before giving up, for the variants which record it.
+    pub fn elapsed(&self) -> Option<Duration> {
+        match self {
+            Failure::Timeout { elapsed, .. }
+            | Failure::Err { elapsed, .. }
+            | Failure::NonRetryable { elapsed, .. } => Some(*elapsed),
+            Failure::Exhausted { .. }
+            | Failure::MaxRetries { .. }
+            | Failure::Cancelled { .. }
+            | Failure::StopRequested { .. }
+            | Failure::FactoryError { .. }
+            | Failure::SuccessRateUnmet { .. } => None,
+        }
+    }
+
+    /// How many attempts were made before the Restartable gave up. This is `restarts() + 1`,
+    /// because the first attempt isn't a restart.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let failure = Restartable::retry_n(|| async { 1 }, |n| Err::<(), _>(n), 3)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert_eq!(failure.restarts(), 2);
+    /// assert_eq!(failure.attempts(), 3);
+    /// # }
+    /// ```
+    pub fn attempts(&self) -> usize {
+        self.restarts().saturating_add(1)
+    }
+
+    /// Whether running the whole retry loop again later might succeed.
+    ///
+    /// Running out of time or attempts is usually transient, so those are retryable later.
+    /// Cancellation, or a requested stop, means someone decided to stop, so it isn't. Neither is
+    /// a factory error, since the factory would most likely fail the same way again, nor an error
+    /// which was classified as not worth retrying.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let failure = Restartable::retry_n(|| async { 400 }, |status| Err::<(), _>(status), 3)
+    ///     .retry_if(|status| *status >= 500)
+    ///     .await
+    ///     .unwrap_err();
+    /// assert!(!failure.is_retryable_later());
+    /// # }
+    /// ```
+    pub fn is_retryable_later(&self) -> bool {
+        match self {
+            Failure::Timeout { .. }
+            | Failure::Err { .. }
+            | Failure::Exhausted { .. }
+            | Failure::MaxRetries { .. }
+            | Failure::SuccessRateUnmet { .. } => true,
+            Failure::Cancelled { .. }
+            | Failure::StopRequested { .. }
+            | Failure::FactoryError { .. }
+            | Failure::NonRetryable { .. } => false,
+        }
+    }
+
+    /// Converts this failure into a [`Stopped`], so it can be matched on as a single shape.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable, StopReason};
+    /// use std::convert::TryFrom;
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let failure = Restartable::new(|| async { 1 }, Some(Duration::from_millis(10)), |n| Err::<(), _>(n))
+    ///     .await
+    ///     .unwrap_err();
+    /// let elapsed = failure.elapsed().unwrap();
+    /// let stopped = failure.into_stopped();
+    /// assert_eq!(stopped.reason, StopReason::Timeout);
+    /// assert_eq!(stopped.error, Some(1));
+    /// assert_eq!(stopped.elapsed, Some(elapsed));
+    ///
+    /// // Converting back keeps the elapsed time.
+    /// let failure = Failure::try_from(stopped).unwrap();
+    /// assert_eq!(failure.elapsed(), Some(elapsed));
+    /// # }
+    /// ```
+    pub fn into_stopped(self) -> Stopped<E> {
+        self.into()
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Failure<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Failure::Timeout {
+                restarts,
+                scope: TimeoutScope::Overall,
+                ..
+            } => write!(f, "timed out after {} restarts", restarts),
+            Failure::Timeout {
+                restarts,
+                scope: TimeoutScope::Attempt,
+                ..
+            } => write!(f, "last attempt timed out after {} restarts", restarts),
+            Failure::Err {
+                error, restarts, ..
+            } => write!(
+                f,
+                "timed out after {} restarts, last error: {}",
+                restarts, error
+            ),
+            Failure::Exhausted { error, restarts } => write!(
+                f,
+                "ran out of attempts after {} restarts, last error: {}",
+                restarts, error
+            ),
+            Failure::MaxRetries { error, restarts } => write!(
+                f,
+                "hit the cap of {} restarts, last error: {}",
+                restarts, error
+            ),
+            Failure::Cancelled { restarts } => write!(f, "cancelled after {} restarts", restarts),
+            Failure::StopRequested { restarts } => {
+                write!(f, "stop requested after {} restarts", restarts)
+            }
+            Failure::FactoryError { error, restarts } => write!(
+                f,
+                "couldn't build the next attempt after {} restarts: {}",
+                restarts, error
+            ),
+            Failure::NonRetryable {
+                error, restarts, ..
+            } => write!(
+                f,
+                "gave up after {} restarts on an error not worth retrying: {}",
+                restarts, error
+            ),
+            Failure::SuccessRateUnmet { restarts } => write!(
+                f,
+                "ran out of attempts after {} restarts before enough of them passed",
+                restarts
+            ),
+        }
+    }
+}
+
+/// Lets `?` propagate a failure into `Box<dyn Error>`, or any error type with a
+/// `From<Failure<E>>` impl. The test's last error, if there is one, is the source.
+///
+/// ```
+/// use restartables::{Failure, Restartable};
+/// use std::error::Error;
+/// use std::num::ParseIntError;
+///
+/// async fn parse_retrying(s: &'static str) -> Result<u8, Box<dyn Error>> {
+///     let retrying = Restartable::retry_n(|| async { s }, |s: &str| s.parse::<u8>(), 2);
+///     let success = retrying.await?;
+///     Ok(success.value)
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// assert_eq!(parse_retrying("7").await.unwrap(), 7);
+/// let err = parse_retrying("seven").await.unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "ran out of attempts after 1 restarts, last error: invalid digit found in string"
+/// );
+/// let failure = err.downcast_ref::<Failure<ParseIntError>>().unwrap();
+/// assert!(failure.source().unwrap().is::<ParseIntError>());
+/// # }
+/// ```
+impl<E: Error + 'static> Error for Failure<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Failure::Err { error, .. }
+            | Failure::Exhausted { error, .. }
+            | Failure::MaxRetries { error, .. }
+            | Failure::FactoryError { error, .. }
+            | Failure::NonRetryable { error, .. } => Some(error),
+            Failure::Timeout { .. }
+            | Failure::Cancelled { .. }
+            | Failure::StopRequested { .. }
+            | Failure::SuccessRateUnmet { .. } => None,
+        }
+    }
 }
+
+/// Which timeout a [`Failure::Timeout`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeoutScope {
+    /// The Restartable's overall timeout expired
+    Overall,
+    /// The last allowed attempt ran past its own timeout, from
+    /// [`Restartable::attempt_timeout_fn`](crate::Restartable::attempt_timeout_fn)
+    Attempt,
+}
+
+/// The stop condition that caused a Restartable to give up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StopReason {
+    /// The timeout expired
+    Timeout,
+    /// Every allowed attempt failed the test
+    Exhausted,
+    /// Every restart allowed by [`Restartable::max_restarts`](crate::Restartable::max_restarts)
+    /// failed the test
+    MaxRetries,
+    /// The Restartable was cancelled
+    Cancelled,
+    /// The predicate from [`Restartable::until`](crate::Restartable::until) said to stop
+    StopRequested,
+    /// The factory from [`try_factory`](crate::try_factory) couldn't build an attempt
+    FactoryError,
+    /// The test failed with an error which isn't worth retrying
+    NonRetryable,
+    /// The attempts ran out before enough of them passed, under
+    /// [`Restartable::require_success_rate`](crate::Restartable::require_success_rate)
+    SuccessRateUnmet,
+}
+
+/// An alternative representation of [`Failure`], with one shape for every stop condition.
+///
+/// Matching on `Failure` means handling each variant separately. If you only care about why the
+/// Restartable stopped, and whether the test produced an error, convert the failure into this
+/// struct instead.
+#[derive(Debug)]
+pub struct Stopped<E> {
+    /// Which stop condition caused the Restartable to give up
+    pub reason: StopReason,
+    /// The last error returned by the test, if the inner future ever resolved
+    pub error: Option<E>,
+    /// How many times the future was restarted before the Restartable gave up
+    pub restarts: usize,
+    /// How long the Restartable ran before giving up, if the stop condition records it
+    pub elapsed: Option<Duration>,
+}
+
+impl<E> From<Failure<E>> for Stopped<E> {
+    fn from(failure: Failure<E>) -> Self {
+        let reason = failure.reason();
+        let elapsed = failure.elapsed();
+        let (error, restarts) = match failure {
+            Failure::Timeout { restarts, .. } => (None, restarts),
+            Failure::Err {
+                error, restarts, ..
+            }
+            | Failure::NonRetryable {
+                error, restarts, ..
+            }
+            | Failure::Exhausted { error, restarts }
+            | Failure::MaxRetries { error, restarts }
+            | Failure::FactoryError { error, restarts } => (Some(error), restarts),
+            Failure::Cancelled { restarts }
+            | Failure::StopRequested { restarts }
+            | Failure::SuccessRateUnmet { restarts } => (None, restarts),
+        };
+        Stopped {
+            reason,
+            error,
+            restarts,
+            elapsed,
+        }
+    }
+}
+
+impl<E> TryFrom<Stopped<E>> for Failure<E> {
+    type Error = UnmatchedStop<E>;
+
+    /// Converts back to the matching `Failure` variant.
+    ///
+    /// `Stopped` doesn't record which timeout expired, or any errors but the last, so timeouts
+    /// always get `TimeoutScope::Overall`, and there are no `recent_errors` or `dropped_errors`.
+    /// A missing `elapsed` becomes zero. Combinations which no Restartable produces, like running
+    /// out of attempts without an error, or being cancelled with one, are given back in an
+    /// [`UnmatchedStop`].
+    fn try_from(stopped: Stopped<E>) -> Result<Self, Self::Error> {
+        let elapsed = stopped.elapsed.unwrap_or_default();
+        let restarts = stopped.restarts;
+        Ok(match (stopped.reason, stopped.error) {
+            (StopReason::Timeout, None) => Failure::Timeout {
+                restarts,
+                scope: TimeoutScope::Overall,
+                elapsed,
+            },
+            (StopReason::Timeout, Some(error)) => Failure::Err {
+                error,
+                restarts,
+                elapsed,
+                recent_errors: Vec::new(),
+                dropped_errors: 0,
+            },
+            (StopReason::Exhausted, Some(error)) => Failure::Exhausted { error, restarts },
+            (StopReason::MaxRetries, Some(error)) => Failure::MaxRetries { error, restarts },
+            (StopReason::FactoryError, Some(error)) => Failure::FactoryError { error, restarts },
+            (StopReason::NonRetryable, Some(error)) => Failure::NonRetryable {
+                error,
+                restarts,
+                elapsed,
+                recent_errors: Vec::new(),
+                dropped_errors: 0,
+            },
+            (StopReason::Cancelled, None) => Failure::Cancelled { restarts },
+            (StopReason::StopRequested, None) => Failure::StopRequested { restarts },
+            (StopReason::SuccessRateUnmet, None) => Failure::SuccessRateUnmet { restarts },
+            (
+                reason @ (StopReason::Exhausted
+                | StopReason::MaxRetries
+                | StopReason::FactoryError
+                | StopReason::NonRetryable),
+                error @ None,
+            )
+            | (
+                reason @ (StopReason::Cancelled
+                | StopReason::StopRequested
+                | StopReason::SuccessRateUnmet),
+                error @ Some(_),
+            ) => {
+                return Err(UnmatchedStop(Stopped {
+                    reason,
+                    error,
+                    restarts,
+                    elapsed: stopped.elapsed,
+                }))
+            }
+        })
+    }
+}
+
+/// The error from converting a [`Stopped`] into a [`Failure`], when no `Failure` has its
+/// combination of reason and error, e.g. [`StopReason::Exhausted`] without an error. Holds on to
+/// the `Stopped`, so nothing is lost.
+#[derive(Debug)]
+pub struct UnmatchedStop<E>(pub Stopped<E>);
+
+impl<E> fmt::Display for UnmatchedStop<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let with = if self.0.error.is_some() {
+            "with"
+        } else {
+            "without"
+        };
+        write!(
+            f,
+            "no Failure stops for {:?} {} an error",
+            self.0.reason, with
+        )
+    }
+}
+
+impl<E: fmt::Debug> Error for UnmatchedStop<E> {}
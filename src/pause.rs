@@ -0,0 +1,79 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// A shared handle for pausing and resuming retry loops, e.g. during a maintenance window.
+///
+/// Pass clones to [`Restartable::with_pauser`](crate::Restartable::with_pauser). While paused,
+/// those `Restartable`s stay pending without polling their attempts, and the time doesn't count
+/// towards their timeouts. Resuming wakes them up again.
+#[derive(Debug, Clone, Default)]
+pub struct Pauser {
+    inner: Arc<Mutex<PauseState>>,
+}
+
+#[derive(Debug, Default)]
+struct PauseState {
+    paused_since: Option<Instant>,
+    /// Every finished pause, added up.
+    paused_total: Duration,
+    /// Tasks waiting for a resume.
+    wakers: Vec<Waker>,
+}
+
+impl Pauser {
+    /// A handle which starts out resumed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hold off on any more work until [`Pauser::resume`] is called. Does nothing if already
+    /// paused.
+    pub fn pause(&self) {
+        let mut state = self.lock();
+        if state.paused_since.is_none() {
+            state.paused_since = Some(Instant::now());
+        }
+    }
+
+    /// Let paused loops carry on. Does nothing if not paused.
+    pub fn resume(&self) {
+        let mut state = self.lock();
+        if let Some(paused_since) = state.paused_since.take() {
+            state.paused_total += paused_since.elapsed();
+            for waker in state.wakers.drain(..) {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Whether this is currently paused.
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused_since.is_some()
+    }
+
+    /// How long this has spent paused, including the current pause, if there is one.
+    pub(crate) fn paused_total(&self) -> Duration {
+        let state = self.lock();
+        let current = state
+            .paused_since
+            .map_or(Duration::ZERO, |since| since.elapsed());
+        state.paused_total + current
+    }
+
+    /// Ready if resumed. Otherwise, wakes the task when it's resumed.
+    pub(crate) fn poll_resumed(&self, cx: &mut Context) -> Poll<()> {
+        let mut state = self.lock();
+        if state.paused_since.is_none() {
+            return Poll::Ready(());
+        }
+        if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PauseState> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
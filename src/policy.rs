@@ -0,0 +1,195 @@
+#[cfg(feature = "global_defaults")]
+use crate::Resolve;
+use crate::{Backoff, Restartable};
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How to retry, separate from what to retry.
+///
+/// Build a policy once, then [`apply`](RetryPolicy::apply) it to any factory and test. This lets
+/// one factory run under different policies at different call sites, and lets a codebase share
+/// policies. The default policy has no timeout and no limits, like `Restartable::new` with a
+/// `None` timeout. Settings which a policy doesn't set are left at their defaults, which come from
+/// the global defaults if the `global_defaults` feature is on.
+///
+/// ```
+/// use restartables::{Failure, RetryPolicy};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let patient = RetryPolicy::builder().timeout(Duration::from_secs(1)).build();
+/// let impatient = RetryPolicy::builder().max_attempts(3).build();
+///
+/// let factory = || async { rand::random::<u8>() };
+/// let is_zero = |n| if n == 0 { Ok(n) } else { Err(n) };
+///
+/// assert!(patient.apply(factory, is_zero).await.is_ok());
+/// match impatient.apply(|| async { 1 }, is_zero).await {
+///     Err(Failure::Exhausted { restarts, .. }) => assert_eq!(restarts, 2),
+///     other => panic!("expected exhaustion, got {:?}", other),
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RetryPolicy {
+    timeout: Option<Duration>,
+    max_attempts: Option<usize>,
+    attempt_timeout: Option<Duration>,
+    max_pending_polls: Option<usize>,
+    yield_between_attempts: bool,
+    backoff: Option<PolicyBackoff>,
+}
+
+/// Makes a fresh copy of a policy's backoff for every Restartable, so they don't share progress.
+#[derive(Clone)]
+struct PolicyBackoff(Arc<dyn Fn() -> Box<dyn Backoff + Send + Sync> + Send + Sync>);
+
+impl PolicyBackoff {
+    fn new<B>(backoff: B) -> Self
+    where
+        B: Backoff + Clone + Send + Sync + 'static,
+    {
+        PolicyBackoff(Arc::new(move || Box::new(backoff.clone())))
+    }
+
+    fn make(&self) -> Box<dyn Backoff + Send + Sync> {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for PolicyBackoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PolicyBackoff(..)")
+    }
+}
+
+impl RetryPolicy {
+    /// Starts configuring a `RetryPolicy`.
+    pub fn builder() -> RetryPolicyBuilder {
+        RetryPolicyBuilder::default()
+    }
+
+    /// Makes a [`Restartable`] which retries `factory`'s futures until they pass `test`, following
+    /// this policy.
+    pub fn apply<Fut, Test, Factory, T, E>(
+        &self,
+        factory: Factory,
+        test: Test,
+    ) -> Restartable<Fut, Test, Factory, T, E>
+    where
+        Fut: Future,
+        Factory: Fn() -> Fut,
+        Test: Fn(Fut::Output) -> Result<T, E>,
+    {
+        let mut restartable = Restartable::new(factory, self.timeout, test);
+        if self.yield_between_attempts {
+            restartable = restartable.yield_between_attempts(true);
+        }
+        if let Some(n) = self.max_attempts {
+            restartable.max_attempts = Some(n);
+        }
+        if let Some(n) = self.max_pending_polls {
+            restartable = restartable.max_pending_polls(n);
+        }
+        if let Some(attempt_timeout) = self.attempt_timeout {
+            restartable = restartable.attempt_timeout_fn(move |_| attempt_timeout);
+        }
+        if let Some(backoff) = &self.backoff {
+            restartable.backoff = Some(backoff.make());
+        }
+        restartable
+    }
+
+    /// Sets whatever this policy sets, on a Restartable which hasn't set it yet.
+    #[cfg(feature = "global_defaults")]
+    pub(crate) fn fill_in<Fut, Test, Factory, T, E>(
+        &self,
+        restartable: &mut Restartable<Fut, Test, Factory, T, E>,
+    ) where
+        Fut: Future,
+        Factory: Fn() -> Fut,
+        Test: Resolve<Fut::Output, Ok = T, Err = E>,
+    {
+        // An explicit deadline is the loop's time budget, so don't add a default one on top.
+        if restartable.timeout.is_none() && restartable.deadline.is_none() {
+            restartable.timeout = self.timeout;
+        }
+        if restartable.max_attempts.is_none() {
+            restartable.max_attempts = self.max_attempts;
+        }
+        if restartable.max_pending_polls.is_none() {
+            restartable.max_pending_polls = self.max_pending_polls;
+        }
+        if restartable.attempt_timeout.is_none() {
+            if let Some(attempt_timeout) = self.attempt_timeout {
+                // Like `attempt_timeout_fn`, this also times the attempt which is about to start.
+                restartable.timed = true;
+                restartable.attempt_limit = Some(attempt_timeout);
+                restartable.attempt_timeout = Some(Box::new(move |_| attempt_timeout));
+            }
+        }
+        if restartable.backoff.is_none() {
+            restartable.backoff = self.backoff.as_ref().map(PolicyBackoff::make);
+        }
+        restartable.yield_between_attempts |= self.yield_between_attempts;
+    }
+}
+
+/// Configures a [`RetryPolicy`].
+#[derive(Debug, Default)]
+pub struct RetryPolicyBuilder {
+    policy: RetryPolicy,
+}
+
+impl RetryPolicyBuilder {
+    /// The overall timeout for the retry loop. Defaults to no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.policy.timeout = Some(timeout);
+        self
+    }
+
+    /// Make at most `n` attempts, resolving to [`Failure::Exhausted`](crate::Failure::Exhausted)
+    /// if they all fail the test. Defaults to unlimited attempts.
+    pub fn max_attempts(mut self, n: usize) -> Self {
+        self.policy.max_attempts = Some(n);
+        self
+    }
+
+    /// Restart any attempt which is still pending after `timeout`. Like
+    /// [`Restartable::attempt_timeout_fn`], with the same timeout for every attempt. Defaults to no
+    /// per-attempt timeout.
+    pub fn attempt_timeout(mut self, timeout: Duration) -> Self {
+        self.policy.attempt_timeout = Some(timeout);
+        self
+    }
+
+    /// See [`Restartable::max_pending_polls`]. Defaults to no limit.
+    pub fn max_pending_polls(mut self, n: usize) -> Self {
+        self.policy.max_pending_polls = Some(n);
+        self
+    }
+
+    /// Wait according to `backoff` after every failed attempt, like [`Restartable::backoff`].
+    /// Every Restartable the policy makes gets its own clone of `backoff`, so each one starts from
+    /// the first delay. Defaults to no backoff, so failed attempts restart straight away.
+    pub fn backoff<B>(mut self, backoff: B) -> Self
+    where
+        B: Backoff + Clone + Send + Sync + 'static,
+    {
+        self.policy.backoff = Some(PolicyBackoff::new(backoff));
+        self
+    }
+
+    /// See [`Restartable::yield_between_attempts`]. Defaults to `false`.
+    pub fn yield_between_attempts(mut self, enabled: bool) -> Self {
+        self.policy.yield_between_attempts = enabled;
+        self
+    }
+
+    pub fn build(self) -> RetryPolicy {
+        self.policy
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{Resolve, Restartable};
+use std::collections::VecDeque;
+use std::future::Future;
+
+/// The sliding window of recent results for [`Restartable::require_success_rate`].
+pub(crate) struct SuccessRate<T, Raw> {
+    window: usize,
+    ratio: f64,
+    /// Whether each of the most recent attempts passed the test, oldest first.
+    recent: VecDeque<bool>,
+    /// The most recent value which passed the test, and its raw output.
+    pub(crate) latest: Option<(T, Option<Raw>)>,
+}
+
+impl<T, Raw> SuccessRate<T, Raw> {
+    fn new(window: usize, ratio: f64) -> Self {
+        let window = window.max(1);
+        SuccessRate {
+            window,
+            // A NaN ratio could never be met, so it asks for every attempt to pass.
+            ratio: if ratio.is_nan() {
+                1.0
+            } else {
+                ratio.clamp(0.0, 1.0)
+            },
+            recent: VecDeque::with_capacity(window),
+            latest: None,
+        }
+    }
+
+    /// How many attempts the window holds.
+    pub(crate) fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Adds an attempt's result to the window, pushing the oldest one out if it's full.
+    pub(crate) fn record(&mut self, passed: bool) {
+        if self.recent.len() == self.window {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(passed);
+    }
+
+    /// Whether the window is full, and enough of it passed.
+    pub(crate) fn is_met(&self) -> bool {
+        let passed = self.recent.iter().filter(|&&passed| passed).count();
+        self.recent.len() == self.window && passed as f64 / self.window as f64 >= self.ratio
+    }
+
+    /// The same window, for a Restartable which keeps a different kind of raw output.
+    pub(crate) fn forget_raw<R>(self) -> SuccessRate<T, R> {
+        SuccessRate {
+            window: self.window,
+            ratio: self.ratio,
+            recent: self.recent,
+            latest: self.latest.map(|(value, _)| (value, None)),
+        }
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Only resolve once at least `ratio` of the last `window` attempts passed the test, instead
+    /// of on the first pass.
+    ///
+    /// This is for stability gating, e.g. a deployment's health check which should pass
+    /// consistently, not just once. Every attempt's result goes into a sliding window, and the
+    /// loop resolves as soon as the window is full and the share of passes in it is at least
+    /// `ratio`, with the most recent value which passed. Until then, passing attempts are
+    /// restarted just like failing ones, after the same [`backoff`](Restartable::backoff) and
+    /// [`min_retry_interval`](Restartable::min_retry_interval). If the backoff says to stop
+    /// after a pass, the loop fails with [`Failure::SuccessRateUnmet`]. `window` is at least 1,
+    /// and `ratio` is clamped to between 0 and 1. A NaN `ratio` is treated as 1.
+    ///
+    /// The window needs `window` attempts to fill, so the timeout has to leave time for at least
+    /// that many. If the timeout expires before the rate is met, the loop fails as usual, with
+    /// [`Failure::Err`] if the last attempt failed, or [`Failure::Timeout`] if it passed. If the
+    /// attempts run out first, it fails with [`Failure::Exhausted`] or [`Failure::MaxRetries`] if
+    /// the last attempt failed, or [`Failure::SuccessRateUnmet`] if it passed.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Every third health check fails.
+    /// let checks = AtomicUsize::new(0);
+    /// let check = || async { checks.fetch_add(1, Ordering::SeqCst) };
+    /// let healthy = |n: usize| if n % 3 == 2 { Err(n) } else { Ok(n) };
+    /// let success = Restartable::new(check, Some(Duration::from_secs(1)), healthy)
+    ///     .require_success_rate(5, 0.6)
+    ///     .await
+    ///     .unwrap();
+    /// // Checks 0 to 4 are three passes and two failures, which is 60%.
+    /// assert_eq!(success.value, 4);
+    /// assert_eq!(success.restarts, 4);
+    /// # }
+    /// ```
+    ///
+    /// [`Failure::Err`]: crate::Failure::Err
+    /// [`Failure::Timeout`]: crate::Failure::Timeout
+    /// [`Failure::Exhausted`]: crate::Failure::Exhausted
+    /// [`Failure::MaxRetries`]: crate::Failure::MaxRetries
+    /// [`Failure::SuccessRateUnmet`]: crate::Failure::SuccessRateUnmet
+    pub fn require_success_rate(mut self, window: usize, ratio: f64) -> Self {
+        self.success_rate = Some(SuccessRate::new(window, ratio));
+        self
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{Resolve, Restartable};
+use std::collections::VecDeque;
+use std::future::Future;
+
+/// Keeps the most recent errors from the test, for [`Restartable::keep_recent_errors`].
+///
+/// This is boxed as a `Send + Sync` trait object, so a Restartable which keeps errors doesn't
+/// stop being `Send` or `Sync` when its error type isn't.
+pub(crate) trait ErrorLog<E> {
+    /// Adds a copy of an attempt's error, pushing the oldest one out if the log's full.
+    fn record(&mut self, error: &E);
+    /// Empties the log, oldest first, along with how many errors were dropped to make room.
+    fn take(&mut self) -> (Vec<E>, usize);
+}
+
+struct RecentErrors<E> {
+    limit: usize,
+    /// Oldest first.
+    errors: VecDeque<E>,
+    /// How many errors were pushed out, or never kept, because the log was full.
+    dropped: usize,
+}
+
+impl<E: Clone> ErrorLog<E> for RecentErrors<E> {
+    fn record(&mut self, error: &E) {
+        if self.limit == 0 {
+            self.dropped = self.dropped.saturating_add(1);
+            return;
+        }
+        if self.errors.len() == self.limit {
+            self.errors.pop_front();
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        self.errors.push_back(error.clone());
+    }
+
+    fn take(&mut self) -> (Vec<E>, usize) {
+        let dropped = std::mem::take(&mut self.dropped);
+        (self.errors.drain(..).collect(), dropped)
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Keep the last `n` errors from the test, and return them in the `recent_errors` of
+    /// [`Failure::Err`](crate::Failure::Err) or
+    /// [`Failure::NonRetryable`](crate::Failure::NonRetryable), for debugging an operation which
+    /// keeps failing in different ways.
+    ///
+    /// The buffer never holds more than `n` errors, so a loop which runs for a long time doesn't
+    /// use more and more memory. They're oldest first, and the last is a copy of the `Failure`'s
+    /// own `error`. The `Failure`'s `dropped_errors` counts the older errors which were pushed
+    /// out, so it's clear whether `recent_errors` is the whole story. Without this,
+    /// `recent_errors` is always empty, and `dropped_errors` is 0.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable};
+    /// use std::sync::atomic::{AtomicU8, Ordering};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Fails with 0, 1, 2, 3, then 4, which isn't worth retrying.
+    /// let calls = AtomicU8::new(0);
+    /// let flapping = || async { calls.fetch_add(1, Ordering::SeqCst) };
+    /// let retrying = Restartable::new(flapping, None, |n: u8| Err::<(), _>(n))
+    ///     .retry_if(|n| *n < 4)
+    ///     .keep_recent_errors(3);
+    /// match retrying.await {
+    ///     Err(Failure::NonRetryable { error, recent_errors, dropped_errors, .. }) => {
+    ///         assert_eq!(error, 4);
+    ///         assert_eq!(recent_errors, vec![2, 3, 4]);
+    ///         assert_eq!(dropped_errors, 2);
+    ///     }
+    ///     other => panic!("expected to give up, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    pub fn keep_recent_errors(mut self, n: usize) -> Self
+    where
+        E: Clone + Send + Sync + 'static,
+    {
+        self.recent_errors = Some(Box::new(RecentErrors {
+            limit: n,
+            errors: VecDeque::with_capacity(n),
+            dropped: 0,
+        }));
+        self
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{
+    AttemptLog, AttemptRecord, Clock, CompletionReason, Metrics, MetricsSink, Outcome,
+    OutcomeReason, Resolve, Restartable,
+};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+/// Diagnostics for a finished retry loop, from [`Restartable::with_report`] or
+/// [`Restartable::with_full_report`].
+///
+/// Every outcome gets the same report, whether the loop succeeded, failed or was cancelled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RetryReport {
+    /// How many attempts were started, including any which were abandoned before they resolved
+    pub attempts: usize,
+    /// How long the loop ran for, not counting time spent paused
+    pub elapsed: Duration,
+    /// How long the loop spent between attempts, e.g. waiting for a
+    /// [`gate`](Restartable::gate_next_attempt) or a
+    /// [`min_retry_interval`](Restartable::min_retry_interval), rather than running one
+    pub waiting: Duration,
+    /// How the loop finished
+    pub completion: CompletionReason,
+    /// The most recent attempts, oldest first, up to the number given to
+    /// [`Restartable::with_full_report`]. Always empty from [`Restartable::with_report`].
+    pub records: Vec<AttemptRecord>,
+}
+
+impl RetryReport {
+    /// How long the loop spent running attempts, i.e. `elapsed` without the `waiting`. Together
+    /// with `waiting` and `attempts`, this shows where a failed loop's time went, e.g. whether it
+    /// timed out because of slow attempts or long waits between them.
+    pub fn active(&self) -> Duration {
+        self.elapsed.saturating_sub(self.waiting)
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+    T: 'static,
+    E: 'static,
+    Ctx: 'static,
+{
+    /// Runs the loop, and resolves to its outcome along with a [`RetryReport`] about how it went.
+    ///
+    /// This is the one place to get diagnostics about a loop, instead of putting them together
+    /// from a [`MetricsSink`]. Any sink set with [`metrics`](Restartable::metrics) still gets
+    /// every event.
+    ///
+    /// ```
+    /// use restartables::{CompletionReason, Restartable};
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let attempts = AtomicUsize::new(0);
+    /// let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    /// let (outcome, report) = Restartable::new(factory, Some(Duration::from_secs(1)), |n| {
+    ///     if n == 2 { Ok(n) } else { Err(n) }
+    /// })
+    /// .min_retry_interval(Duration::from_millis(5))
+    /// .with_report()
+    /// .await;
+    /// assert_eq!(outcome.unwrap().value, 2);
+    /// assert_eq!(report.attempts, 3);
+    /// assert_eq!(report.completion, CompletionReason::RetriedThenPassed);
+    /// assert!(report.waiting >= Duration::from_millis(10));
+    /// assert!(report.active() <= report.elapsed);
+    /// assert!(report.records.is_empty());
+    /// # }
+    /// ```
+    pub async fn with_report(self) -> (Outcome<T, E, Raw>, RetryReport) {
+        self.reporting(None).await
+    }
+
+    /// Like [`with_report`](Restartable::with_report), but the report also has an
+    /// [`AttemptRecord`] for each of the last `max_records` attempts, like an [`AttemptLog`] with
+    /// that capacity would keep. The cap stops a long-running loop from keeping a record of every
+    /// attempt it ever made.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let (outcome, report) = Restartable::retry_n(|| async { 1 }, |n| Err::<(), _>(n), 2)
+    ///     .with_full_report(10)
+    ///     .await;
+    /// assert!(outcome.is_err());
+    /// assert_eq!(report.records.len(), 2);
+    /// assert_eq!(report.records[1].result, Err("1".to_owned()));
+    /// # }
+    /// ```
+    pub async fn with_full_report(self, max_records: usize) -> (Outcome<T, E, Raw>, RetryReport)
+    where
+        E: fmt::Display,
+    {
+        let log = AttemptLog::with_capacity(max_records);
+        self.reporting(Some((log.clone(), Box::new(log)))).await
+    }
+
+    /// Runs the loop, and builds the report. If there's a `log`, its sink is given every event,
+    /// and the report gets its records.
+    async fn reporting(
+        mut self,
+        log: Option<(AttemptLog, Metrics<T, E, Ctx>)>,
+    ) -> (Outcome<T, E, Raw>, RetryReport) {
+        let (log, log_sink) = match log {
+            Some((log, sink)) => (Some(log), Some(sink)),
+            None => (None, None),
+        };
+        let progress = Arc::new(Mutex::new(Progress::default()));
+        let sink = Reporting {
+            progress: progress.clone(),
+            clock: self.clock.clone(),
+            log: log_sink,
+            inner: self.metrics.take(),
+        };
+        self.timed = true;
+        self.metrics = Some(Box::new(sink));
+        let outcome = self.await;
+        let progress = *progress.lock().unwrap_or_else(PoisonError::into_inner);
+        let report = RetryReport {
+            attempts: progress.attempts,
+            elapsed: progress.elapsed,
+            waiting: progress.waiting,
+            completion: outcome.reason(),
+            records: log.map_or_else(Vec::new, |log| log.records()),
+        };
+        (outcome, report)
+    }
+}
+
+/// What [`Reporting`] has seen so far.
+#[derive(Debug, Clone, Copy, Default)]
+struct Progress {
+    attempts: usize,
+    elapsed: Duration,
+    waiting: Duration,
+    /// When the last attempt resolved or was abandoned, by the loop's clock, until the next one
+    /// starts.
+    last_end: Option<Instant>,
+}
+
+/// Keeps a [`RetryReport`] up to date, and passes every event on to the Restartable's own sink,
+/// if it has one.
+struct Reporting<T, E, Ctx> {
+    progress: Arc<Mutex<Progress>>,
+    /// The Restartable's clock, so the time between attempts is measured like everything else.
+    clock: Arc<dyn Clock + Send + Sync>,
+    log: Option<Metrics<T, E, Ctx>>,
+    inner: Option<Metrics<T, E, Ctx>>,
+}
+
+impl<T, E, Ctx> Reporting<T, E, Ctx> {
+    fn lock(&self) -> MutexGuard<'_, Progress> {
+        self.progress.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Starts counting the time until the next attempt.
+    fn ended(&self) {
+        self.lock().last_end = Some(self.clock.now());
+    }
+}
+
+impl<T, E, Ctx> MetricsSink<T, E, Ctx> for Reporting<T, E, Ctx> {
+    fn on_attempt_start(&self, attempt: usize, ctx: &Ctx) {
+        {
+            let mut progress = self.lock();
+            progress.attempts = attempt.saturating_add(1);
+            if let Some(last_end) = progress.last_end.take() {
+                progress.waiting += self.clock.now().saturating_duration_since(last_end);
+            }
+        }
+        if let Some(log) = self.log.as_ref() {
+            log.on_attempt_start(attempt, ctx);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_start(attempt, ctx);
+        }
+    }
+
+    fn on_attempt_end(&self, result: &Result<T, E>, duration: Duration, ctx: &Ctx) {
+        self.ended();
+        if let Some(log) = self.log.as_ref() {
+            log.on_attempt_end(result, duration, ctx);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_end(result, duration, ctx);
+        }
+    }
+
+    fn on_attempt_abandoned(&self, attempt: usize, duration: Duration, ctx: &Ctx) {
+        self.ended();
+        if let Some(log) = self.log.as_ref() {
+            log.on_attempt_abandoned(attempt, duration, ctx);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_attempt_abandoned(attempt, duration, ctx);
+        }
+    }
+
+    fn on_complete(
+        &self,
+        reason: CompletionReason,
+        duration: Duration,
+        restarts: usize,
+        ctx: &Ctx,
+    ) {
+        self.lock().elapsed = duration;
+        if let Some(log) = self.log.as_ref() {
+            log.on_complete(reason, duration, restarts, ctx);
+        }
+        if let Some(inner) = self.inner.as_ref() {
+            inner.on_complete(reason, duration, restarts, ctx);
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Helpers for retrying [`reqwest`] requests. Requires the `use_reqwest` feature.
+//!
+//! ```no_run
+//! use restartables::reqw::RetryingClient;
+//! use std::time::Duration;
+//!
+//! # async fn run() {
+//! // Configure the retry defaults once...
+//! let client = RetryingClient::builder()
+//!     .timeout(Duration::from_secs(2))
+//!     .build();
+//!
+//! // ...then reuse them for every request.
+//! let url = reqwest::Url::parse("https://example.com").unwrap();
+//! let req = reqwest::Request::new(reqwest::Method::GET, url);
+//! let outcome = client
+//!     .execute_retrying(
+//!         req,
+//!         |resp| match resp {
+//!             Ok(resp) if resp.status().is_success() => Ok(resp),
+//!             Ok(resp) => Err(resp.status().to_string()),
+//!             Err(e) => Err(e.to_string()),
+//!         },
+//!         None,
+//!     )
+//!     .await;
+//! # }
+//! ```
+
+use crate::{Outcome, Restartable};
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A [`reqwest::Client`] which retries requests, using retry settings configured once.
+#[derive(Clone, Default)]
+pub struct RetryingClient {
+    client: reqwest::Client,
+    timeout: Option<Duration>,
+    on_response: Option<OnResponse>,
+}
+
+/// Observer for [`RetryingClientBuilder::on_response`].
+type OnResponse = Arc<dyn Fn(&reqwest::Response, usize) + Send + Sync>;
+
+impl fmt::Debug for RetryingClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingClient")
+            .field("client", &self.client)
+            .field("timeout", &self.timeout)
+            .field("on_response", &self.on_response.is_some())
+            .finish()
+    }
+}
+
+impl RetryingClient {
+    /// Wraps `client`, with no default timeout.
+    pub fn new(client: reqwest::Client) -> Self {
+        RetryingClient {
+            client,
+            timeout: None,
+            on_response: None,
+        }
+    }
+
+    /// Starts configuring a `RetryingClient`.
+    pub fn builder() -> RetryingClientBuilder {
+        RetryingClientBuilder::default()
+    }
+
+    /// The wrapped client, for requests which shouldn't be retried.
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Executes `req`, retrying until the response passes `test`, or the timeout expires.
+    ///
+    /// `timeout` overrides this client's default timeout for this request only. If it's `None`,
+    /// the default is used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `req` can't be cloned, i.e. if its body is a stream. Use
+    /// [`RetryingClient::send_retrying`] for those requests instead.
+    pub async fn execute_retrying<T, E, Test>(
+        &self,
+        req: reqwest::Request,
+        test: Test,
+        timeout: Option<Duration>,
+    ) -> Outcome<T, E>
+    where
+        Test: Fn(reqwest::Result<reqwest::Response>) -> Result<T, E>,
+    {
+        let attempts = AtomicUsize::new(0);
+        let factory = || {
+            let req = req
+                .try_clone()
+                .expect("streaming request bodies can't be retried");
+            self.observe(&attempts, self.client.execute(req))
+        };
+        Restartable::new(factory, timeout.or(self.timeout), test).await
+    }
+
+    /// Sends a fresh request from `build` for every attempt, retrying until the response passes
+    /// `test`, or the timeout expires.
+    ///
+    /// Unlike [`RetryingClient::execute_retrying`], this never clones a request, so it works for
+    /// any body. Errors from building the request, e.g. an invalid header, are passed to `test`
+    /// like any other error. `timeout` overrides this client's default timeout for this request
+    /// only.
+    ///
+    /// ```no_run
+    /// use restartables::reqw::RetryingClient;
+    /// use std::time::Duration;
+    ///
+    /// # async fn run() {
+    /// let client = RetryingClient::builder()
+    ///     .timeout(Duration::from_secs(2))
+    ///     .build();
+    /// let outcome = client
+    ///     .send_retrying(
+    ///         || {
+    ///             client
+    ///                 .client()
+    ///                 .get("https://example.com")
+    ///                 .header("X-Request-Id", "1234")
+    ///         },
+    ///         |resp| match resp {
+    ///             Ok(resp) if resp.status().is_success() => Ok(resp),
+    ///             Ok(resp) => Err(resp.status().to_string()),
+    ///             Err(e) => Err(e.to_string()),
+    ///         },
+    ///         None,
+    ///     )
+    ///     .await;
+    /// # }
+    /// ```
+    pub async fn send_retrying<T, E, Build, Test>(
+        &self,
+        build: Build,
+        test: Test,
+        timeout: Option<Duration>,
+    ) -> Outcome<T, E>
+    where
+        Build: Fn() -> reqwest::RequestBuilder,
+        Test: Fn(reqwest::Result<reqwest::Response>) -> Result<T, E>,
+    {
+        let attempts = AtomicUsize::new(0);
+        let factory = || self.observe(&attempts, build().send());
+        Restartable::new(factory, timeout.or(self.timeout), test).await
+    }
+
+    /// Executes `req`, retrying until the response's headers look good, then returns the response
+    /// without reading its body.
+    ///
+    /// This is for large downloads, where only connecting and getting the headers should be
+    /// retried. A response is accepted if its status is a success, and, if `content_type` is given,
+    /// its `Content-Type` has that media type (parameters like `charset` are ignored). Once a
+    /// response is accepted, nothing is retried, so the caller can stream the body with e.g.
+    /// [`reqwest::Response::chunk`], and handle its errors themselves. `timeout` overrides this
+    /// client's default timeout for this request only.
+    ///
+    /// ```no_run
+    /// use restartables::reqw::RetryingClient;
+    ///
+    /// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = RetryingClient::default();
+    /// let url = reqwest::Url::parse("https://example.com/big.iso")?;
+    /// let req = reqwest::Request::new(reqwest::Method::GET, url);
+    /// let mut resp = client
+    ///     .execute_stream(req, Some("application/octet-stream"), None)
+    ///     .await?
+    ///     .value;
+    /// while let Some(_chunk) = resp.chunk().await? {
+    ///     // Write the chunk somewhere.
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `req` can't be cloned, i.e. if its body is a stream.
+    pub async fn execute_stream(
+        &self,
+        req: reqwest::Request,
+        content_type: Option<&str>,
+        timeout: Option<Duration>,
+    ) -> Outcome<reqwest::Response, StartError> {
+        let test = |resp: reqwest::Result<reqwest::Response>| {
+            let resp = resp.map_err(StartError::Request)?;
+            if !resp.status().is_success() {
+                return Err(StartError::Status(resp.status()));
+            }
+            if let Some(expected) = content_type {
+                let actual = resp.headers().get(reqwest::header::CONTENT_TYPE);
+                let media_type = actual
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.split(';').next())
+                    .map(str::trim);
+                if !matches!(media_type, Some(media_type) if media_type.eq_ignore_ascii_case(expected))
+                {
+                    return Err(StartError::ContentType(actual.cloned()));
+                }
+            }
+            Ok(resp)
+        };
+        self.execute_retrying(req, test, timeout).await
+    }
+
+    /// Passes `response` to the `on_response` observer, if there is one, with the next attempt's
+    /// index from `attempts`.
+    fn observe<'a, F>(
+        &'a self,
+        attempts: &AtomicUsize,
+        response: F,
+    ) -> impl Future<Output = reqwest::Result<reqwest::Response>> + 'a
+    where
+        F: Future<Output = reqwest::Result<reqwest::Response>> + 'a,
+    {
+        let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+        async move {
+            let response = response.await;
+            if let (Ok(response), Some(on_response)) = (&response, &self.on_response) {
+                on_response(response, attempt);
+            }
+            response
+        }
+    }
+}
+
+/// Why a response wasn't accepted by [`RetryingClient::execute_stream`].
+#[derive(Debug)]
+pub enum StartError {
+    /// The request failed before there was a response
+    Request(reqwest::Error),
+    /// The response's status wasn't a success
+    Status(reqwest::StatusCode),
+    /// The response's `Content-Type` was missing, or wasn't the expected one
+    ContentType(Option<reqwest::header::HeaderValue>),
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::Request(e) => write!(f, "request failed: {}", e),
+            StartError::Status(status) => write!(f, "unsuccessful status: {}", status),
+            StartError::ContentType(Some(content_type)) => {
+                write!(f, "unexpected content type: {:?}", content_type)
+            }
+            StartError::ContentType(None) => write!(f, "missing content type"),
+        }
+    }
+}
+
+impl std::error::Error for StartError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StartError::Request(e) => Some(e),
+            StartError::Status(_) | StartError::ContentType(_) => None,
+        }
+    }
+}
+
+/// Configures the defaults for a [`RetryingClient`].
+#[derive(Default)]
+pub struct RetryingClientBuilder {
+    client: Option<reqwest::Client>,
+    timeout: Option<Duration>,
+    on_response: Option<OnResponse>,
+}
+
+impl fmt::Debug for RetryingClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryingClientBuilder")
+            .field("client", &self.client)
+            .field("timeout", &self.timeout)
+            .field("on_response", &self.on_response.is_some())
+            .finish()
+    }
+}
+
+impl RetryingClientBuilder {
+    /// Use `client` to send requests. Defaults to `reqwest::Client::default()`.
+    pub fn client(mut self, client: reqwest::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// The default timeout for every request's retry loop. Defaults to no timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Call `f` with every response, and the index of the attempt which got it (0 for the first
+    /// attempt), before the test runs. This is for e.g. logging the status of every attempt.
+    /// Requests which fail without a response aren't observed.
+    ///
+    /// ```
+    /// use restartables::reqw::RetryingClient;
+    ///
+    /// let client = RetryingClient::builder()
+    ///     .on_response(|resp, attempt| println!("attempt {} got {}", attempt, resp.status()))
+    ///     .build();
+    /// ```
+    pub fn on_response<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&reqwest::Response, usize) + Send + Sync + 'static,
+    {
+        self.on_response = Some(Arc::new(f));
+        self
+    }
+
+    pub fn build(self) -> RetryingClient {
+        RetryingClient {
+            client: self.client.unwrap_or_default(),
+            timeout: self.timeout,
+            on_response: self.on_response,
+        }
+    }
+}
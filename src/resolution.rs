@@ -0,0 +1,142 @@
+/// A three-way verdict on an attempt's output, for [`Restartable::with_resolution`].
+///
+/// [`Restartable::with_resolution`]: crate::Restartable::with_resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution<T, E> {
+    /// The value passed, so resolve the Restartable with it now.
+    Accept(T),
+    /// The value is good enough to return, but keep retrying in case a later attempt is better.
+    ///
+    /// The Restartable holds on to the most recent value like this. If a later attempt is
+    /// accepted, that value wins. If the Restartable would otherwise fail, it resolves to a success
+    /// with the held value instead.
+    AcceptButContinue(T),
+    /// The value failed, so restart the inner future.
+    Reject(E),
+}
+
+impl<T, E> Resolution<T, E> {
+    /// Splits this into the test's result, and whether to keep retrying even if it passed.
+    pub(crate) fn split(self) -> (Result<T, E>, bool) {
+        match self {
+            Resolution::Accept(value) => (Ok(value), false),
+            Resolution::AcceptButContinue(value) => (Ok(value), true),
+            Resolution::Reject(error) => (Err(error), false),
+        }
+    }
+}
+
+impl<T, E> From<Result<T, E>> for Resolution<T, E> {
+    /// `Ok` accepts, and `Err` rejects.
+    fn from(result: Result<T, E>) -> Self {
+        match result {
+            Ok(value) => Resolution::Accept(value),
+            Err(error) => Resolution::Reject(error),
+        }
+    }
+}
+
+/// The test a Restartable runs on each attempt's output.
+///
+/// This is implemented for closures returning a `Result`, which is what [`Restartable::new`]
+/// takes, for [`Resolving`], which is what [`Restartable::with_resolution`] takes, for
+/// [`Inverted`], which is what [`Restartable::until_err`] takes, and for [`Numbered`], which is
+/// what [`numbered_test`] takes.
+///
+/// [`numbered_test`]: crate::numbered_test
+/// [`Restartable::new`]: crate::Restartable::new
+/// [`Restartable::with_resolution`]: crate::Restartable::with_resolution
+/// [`Restartable::until_err`]: crate::Restartable::until_err
+pub trait Resolve<I> {
+    /// The value a passing output is mapped to.
+    type Ok;
+    /// The error a failing output is mapped to.
+    type Err;
+
+    /// Decides what to do with an attempt's output.
+    fn resolve(&self, output: I) -> Resolution<Self::Ok, Self::Err>;
+
+    /// Like [`resolve`](Resolve::resolve), but also gets how many times the future had been
+    /// restarted before this attempt. The Restartable calls this, rather than `resolve`. By
+    /// default, it ignores `restarts`.
+    fn resolve_attempt(&self, output: I, restarts: usize) -> Resolution<Self::Ok, Self::Err> {
+        let _ = restarts;
+        self.resolve(output)
+    }
+}
+
+impl<F, I, T, E> Resolve<I> for F
+where
+    F: Fn(I) -> Result<T, E>,
+{
+    type Ok = T;
+    type Err = E;
+
+    fn resolve(&self, output: I) -> Resolution<T, E> {
+        self(output).into()
+    }
+}
+
+/// A test closure which returns a [`Resolution`], for [`Restartable::with_resolution`].
+///
+/// [`Restartable::with_resolution`]: crate::Restartable::with_resolution
+#[derive(Debug, Clone, Copy)]
+pub struct Resolving<F>(pub F);
+
+impl<F, I, T, E> Resolve<I> for Resolving<F>
+where
+    F: Fn(I) -> Resolution<T, E>,
+{
+    type Ok = T;
+    type Err = E;
+
+    fn resolve(&self, output: I) -> Resolution<T, E> {
+        (self.0)(output)
+    }
+}
+
+/// A test closure with inverted semantics, for [`Restartable::until_err`]: `Err` resolves the
+/// Restartable, and `Ok` restarts it.
+///
+/// [`Restartable::until_err`]: crate::Restartable::until_err
+#[derive(Debug, Clone, Copy)]
+pub struct Inverted<F>(pub F);
+
+impl<F, I, T, E> Resolve<I> for Inverted<F>
+where
+    F: Fn(I) -> Result<E, T>,
+{
+    type Ok = T;
+    type Err = E;
+
+    fn resolve(&self, output: I) -> Resolution<T, E> {
+        match (self.0)(output) {
+            Ok(error) => Resolution::Reject(error),
+            Err(value) => Resolution::Accept(value),
+        }
+    }
+}
+
+/// A test closure which also gets how many times the future has been restarted, for
+/// [`numbered_test`].
+///
+/// [`numbered_test`]: crate::numbered_test
+#[derive(Debug, Clone, Copy)]
+pub struct Numbered<F>(pub F);
+
+impl<F, I, T, E> Resolve<I> for Numbered<F>
+where
+    F: Fn(I, usize) -> Result<T, E>,
+{
+    type Ok = T;
+    type Err = E;
+
+    /// Resolves as if this was the first attempt.
+    fn resolve(&self, output: I) -> Resolution<T, E> {
+        self.resolve_attempt(output, 0)
+    }
+
+    fn resolve_attempt(&self, output: I, restarts: usize) -> Resolution<T, E> {
+        (self.0)(output, restarts).into()
+    }
+}
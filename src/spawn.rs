@@ -0,0 +1,118 @@
+use crate::{delay, Backoff, Clock, Resolve, Restartable, Success};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+/// Runs detached background work, e.g. on an async runtime's task pool.
+///
+/// Used by [`Restartable::return_first_and_continue`] to keep retrying after the `Restartable`
+/// has already resolved. Implement this for whichever runtime your application uses.
+pub trait Spawner {
+    /// Run `task` in the background. The caller never waits for it to finish.
+    fn spawn(&self, task: Pin<Box<dyn Future<Output = ()> + Send + 'static>>);
+}
+
+/// A [`Spawner`] which runs background work with [`tokio::spawn`].
+///
+/// Must be used from inside a tokio runtime.
+#[cfg(feature = "use_tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSpawner;
+
+#[cfg(feature = "use_tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, task: Pin<Box<dyn Future<Output = ()> + Send + 'static>>) {
+        tokio::spawn(task);
+    }
+}
+
+/// What a resolved [`Restartable`] hands on to the background loops from
+/// [`Restartable::return_first_and_continue`].
+pub(crate) struct Carried {
+    /// Whatever was left of the timeout and deadline.
+    pub(crate) remaining: Option<Duration>,
+    pub(crate) clock: Arc<dyn Clock + Send + Sync>,
+    pub(crate) max_attempts: Option<usize>,
+    pub(crate) restarts_capped: bool,
+    pub(crate) max_pending_polls: Option<usize>,
+    pub(crate) min_retry_interval: Option<Duration>,
+    pub(crate) attempt_timeout: Option<Arc<dyn Fn(usize) -> Duration + Send + Sync>>,
+    /// Shared by the background loops, which only ever run one at a time.
+    pub(crate) backoff: Option<Arc<Mutex<Box<dyn Backoff + Send + Sync>>>>,
+}
+
+/// One background loop's handle on the backoff they all share.
+struct SharedBackoff(Arc<Mutex<Box<dyn Backoff + Send + Sync>>>);
+
+impl SharedBackoff {
+    fn lock(&self) -> MutexGuard<'_, Box<dyn Backoff + Send + Sync>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Backoff for SharedBackoff {
+    fn delay(&mut self, restarts: usize) -> Duration {
+        self.lock().delay(restarts)
+    }
+
+    fn next_delay(&mut self, restarts: usize) -> Option<Duration> {
+        self.lock().next_delay(restarts)
+    }
+
+    fn can_stop(&self) -> bool {
+        self.lock().can_stop()
+    }
+}
+
+/// Keeps running fresh retry loops, configured like the one which spawned them, until `on_value`
+/// asks to stop, one fails, or the time that was left runs out. Waits for `min_retry_interval`
+/// between loops.
+pub(crate) async fn keep_retrying<Fut, Test, Factory, T, E, F>(
+    factory: Factory,
+    test: Test,
+    on_value: F,
+    carried: Carried,
+) where
+    Fut: Future,
+    Factory: Fn() -> Fut + Clone,
+    Test: Resolve<Fut::Output, Ok = T, Err = E> + Clone,
+    F: Fn(Success<T>) -> bool,
+{
+    let clock = carried.clock;
+    let deadline = carried.remaining.map(|remaining| clock.now() + remaining);
+    loop {
+        let remaining = match deadline {
+            Some(deadline) => match deadline.checked_duration_since(clock.now()) {
+                Some(remaining) => Some(remaining),
+                None => return,
+            },
+            None => None,
+        };
+        // The Restartable which spawned these loops has already warned if it's unbounded.
+        let mut restartable =
+            Restartable::from_parts(factory.clone(), remaining, test.clone()).allow_unbounded();
+        restartable.clock = clock.clone();
+        restartable.max_attempts = carried.max_attempts;
+        restartable.restarts_capped = carried.restarts_capped;
+        restartable.max_pending_polls = carried.max_pending_polls;
+        restartable.min_retry_interval = carried.min_retry_interval;
+        if let Some(attempt_timeout) = carried.attempt_timeout.clone() {
+            restartable = restartable.attempt_timeout_fn(move |restarts| attempt_timeout(restarts));
+        }
+        if let Some(backoff) = carried.backoff.clone() {
+            restartable = restartable.backoff(SharedBackoff(backoff));
+        }
+        match restartable.await {
+            Ok(success) => {
+                if !on_value(success) {
+                    return;
+                }
+            }
+            Err(_) => return,
+        }
+        if let Some(interval) = carried.min_retry_interval {
+            delay::sleep(interval).await;
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{running_time, Resolve, Restartable};
+use std::future::Future;
+use std::time::Duration;
+
+/// A snapshot of a retry loop's progress, which can be saved, and resumed from later.
+///
+/// This is for retries which might outlive the process, like a job which retries for hours. Save
+/// the state from [`Restartable::state`] somewhere durable, e.g. a database, and after a crash or
+/// restart, carry on from it with [`Restartable::resume`]. With the `use_serde` feature, this
+/// implements serde's `Serialize` and `Deserialize`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "use_serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryState {
+    /// How many times the loop had restarted
+    pub restarts: usize,
+    /// How long the loop had been running for, not counting time spent paused
+    pub elapsed: Duration,
+    /// How many attempts had failed and backed off, so a resumed loop's
+    /// [`Backoff`](crate::Backoff) carries on from the next delay, rather than the first
+    pub backoff_position: usize,
+}
+
+impl<Fut, Test, Factory, T, E> Restartable<Fut, Test, Factory, T, E>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+{
+    /// Like [`Restartable::new`], but carries on from a saved [`RetryState`], instead of starting
+    /// afresh.
+    ///
+    /// The state's restarts count towards the `Success` or `Failure`'s restarts, and towards any
+    /// attempt limit. Its elapsed time counts towards `timeout`, and towards `Success::duration`.
+    /// Its backoff position is passed on to the [`Backoff`](crate::Backoff), so e.g. an
+    /// exponential backoff picks up where it left off. The attempt which was running when the
+    /// state was saved is lost, so this starts a fresh one.
+    ///
+    /// ```
+    /// use restartables::{Failure, Restartable, RetryState};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // A previous run of this process used up most of the time budget before it crashed.
+    /// let saved = RetryState {
+    ///     restarts: 40,
+    ///     elapsed: Duration::from_secs(59),
+    ///     backoff_position: 40,
+    /// };
+    /// let timeout = Some(Duration::from_secs(60));
+    /// let retrying = Restartable::resume(saved, || async { 1 }, timeout, |n: u8| Ok::<_, ()>(n));
+    /// let success = retrying.await.unwrap();
+    /// assert_eq!(success.restarts, 40);
+    /// assert!(success.duration >= Duration::from_secs(59));
+    /// # }
+    /// ```
+    pub fn resume(
+        state: RetryState,
+        factory: Factory,
+        timeout: Option<Duration>,
+        test: Test,
+    ) -> Self {
+        let mut restartable = Self::new(factory, timeout, test);
+        restartable.restarts = state.restarts;
+        restartable.resumed_elapsed = state.elapsed;
+        restartable.backoff_position = state.backoff_position;
+        restartable
+    }
+}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// A snapshot of this loop's progress so far, to save and [`resume`](Restartable::resume)
+    /// from later. This can be called between polls of a pinned `Restartable`.
+    pub fn state(&self) -> RetryState {
+        RetryState {
+            restarts: self.restarts,
+            elapsed: running_time(
+                &*self.clock,
+                self.start,
+                self.pauser.as_ref(),
+                self.resumed_elapsed,
+            ),
+            backoff_position: self.backoff_position,
+        }
+    }
+}
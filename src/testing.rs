@@ -0,0 +1,222 @@
+//! Helpers for testing retry configurations. Requires the `testing` feature.
+
+use crate::Clock;
+use pin_project::pin_project;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+/// Wraps a factory, and makes some of its attempts fail on purpose.
+///
+/// Each attempt from [`ChaosFactory::attempt`] still runs the real future, but resolves to
+/// `Err(Injected)` instead of its output if it was chosen to fail. Forced failures come from
+/// [`ChaosFactory::fail_first`], which is deterministic, and
+/// [`ChaosFactory::fail_with_probability`], which isn't. The test should treat `Err(Injected)`
+/// like any other failure.
+///
+/// ```
+/// use restartables::testing::ChaosFactory;
+/// use restartables::Restartable;
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let chaos = ChaosFactory::new(|| async { 7 }).fail_first(3);
+/// let retrying = Restartable::new(|| chaos.attempt(), Some(Duration::from_secs(1)), |output| output);
+/// let success = retrying.await.unwrap();
+/// assert_eq!(success.value, 7);
+/// assert_eq!(success.restarts, 3);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ChaosFactory<Factory> {
+    factory: Factory,
+    fail_first: usize,
+    probability: f64,
+    attempts: AtomicUsize,
+}
+
+impl<Factory, Fut> ChaosFactory<Factory>
+where
+    Factory: Fn() -> Fut,
+    Fut: Future,
+{
+    /// Wraps `factory`, without forcing any failures yet.
+    pub fn new(factory: Factory) -> Self {
+        ChaosFactory {
+            factory,
+            fail_first: 0,
+            probability: 0.0,
+            attempts: AtomicUsize::new(0),
+        }
+    }
+
+    /// Force the first `k` attempts to fail.
+    pub fn fail_first(mut self, k: usize) -> Self {
+        self.fail_first = k;
+        self
+    }
+
+    /// Force each attempt after those from `fail_first` to fail with probability `p`, from 0 to 1.
+    pub fn fail_with_probability(mut self, p: f64) -> Self {
+        self.probability = p;
+        self
+    }
+
+    /// Makes the next attempt, deciding now whether it's forced to fail.
+    pub fn attempt(&self) -> ChaosFuture<Fut> {
+        let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+        let fail = attempt < self.fail_first || rand::random::<f64>() < self.probability;
+        ChaosFuture {
+            inner: (self.factory)(),
+            fail,
+        }
+    }
+
+    /// How many attempts have been made so far.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+}
+
+/// Wraps a factory, and counts how many futures it builds.
+///
+/// Every attempt is built by the factory, including the first, which a `Restartable` builds as
+/// soon as it's made. So this counts attempts independently of `restarts`, which only counts the
+/// attempts after the first. The count is shared through an `Arc`, so it can still be read after
+/// the factory has been moved into a `Restartable`.
+///
+/// ```
+/// use restartables::testing::CountingFactory;
+/// use restartables::Restartable;
+/// use std::sync::atomic::Ordering;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let counting = CountingFactory::new(|| async { 1 });
+/// let built = counting.counter();
+/// let retrying = Restartable::retry_n(move || counting.attempt(), |n: u8| Err::<u8, _>(n), 3);
+/// // The first attempt is built straight away, before the Restartable is polled.
+/// assert_eq!(built.load(Ordering::SeqCst), 1);
+/// let failure = retrying.await.unwrap_err();
+/// assert_eq!(failure.restarts(), 2);
+/// assert_eq!(built.load(Ordering::SeqCst), 3);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct CountingFactory<Factory> {
+    factory: Factory,
+    built: Arc<AtomicUsize>,
+}
+
+impl<Factory, Fut> CountingFactory<Factory>
+where
+    Factory: Fn() -> Fut,
+{
+    /// Wraps `factory`, starting the count at zero.
+    pub fn new(factory: Factory) -> Self {
+        CountingFactory {
+            factory,
+            built: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Builds the next attempt with the wrapped factory, and counts it.
+    pub fn attempt(&self) -> Fut {
+        self.built.fetch_add(1, Ordering::SeqCst);
+        (self.factory)()
+    }
+
+    /// How many attempts have been built so far.
+    pub fn count(&self) -> usize {
+        self.built.load(Ordering::SeqCst)
+    }
+
+    /// The shared count, for reading after this has been moved away.
+    pub fn counter(&self) -> Arc<AtomicUsize> {
+        self.built.clone()
+    }
+}
+
+/// Future returned by [`ChaosFactory::attempt`].
+#[pin_project]
+#[derive(Debug)]
+pub struct ChaosFuture<Fut> {
+    #[pin]
+    inner: Fut,
+    fail: bool,
+}
+
+impl<Fut: Future> Future for ChaosFuture<Fut> {
+    type Output = Result<Fut::Output, Injected>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.project();
+        let fail = *this.fail;
+        this.inner
+            .poll(cx)
+            .map(|output| if fail { Err(Injected) } else { Ok(output) })
+    }
+}
+
+/// The error from an attempt which a [`ChaosFactory`] forced to fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Injected;
+
+impl fmt::Display for Injected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "failure injected by ChaosFactory")
+    }
+}
+
+impl std::error::Error for Injected {}
+
+/// A [`Clock`] which only moves when it's told to, for deterministic timing tests.
+///
+/// Clones share the same time, so keep one to [`advance`](ManualClock::advance) and give another
+/// to [`Restartable::clock`](crate::Restartable::clock).
+///
+/// ```
+/// use restartables::testing::ManualClock;
+/// use restartables::Clock;
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let start = clock.now();
+/// clock.advance(Duration::from_secs(5));
+/// assert_eq!(clock.now() - start, Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// A clock stopped at the current time.
+    pub fn new() -> Self {
+        ManualClock {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Moves the clock, and every clone of it, forward by `by`.
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner) += by;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{Failure, Outcome, Resolve, Restartable, Success};
+use std::future::Future;
+use std::time::Duration;
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Once this retry loop succeeds, runs a second one, built from this one's value, within
+    /// whatever is left of this one's timeout.
+    ///
+    /// This is for operations with several steps, where each step is retried on its own, but the
+    /// whole operation has one time budget. `next_factory` gets the first loop's value, and makes
+    /// the second loop's futures. The second loop's timeout is the first loop's timeout, minus
+    /// however long the first loop took, and it also stops at the first loop's
+    /// [`deadline`](Restartable::deadline), if there is one. If the first loop had no timeout,
+    /// neither does the second.
+    ///
+    /// The result combines both loops: a `Success`'s duration and restarts are the totals across
+    /// both, and so are a `Failure`'s restarts and elapsed time. Moving on to the second loop
+    /// doesn't count as a restart.
+    ///
+    /// ```
+    /// use restartables::Restartable;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::time::Duration;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// // Step one: find a free port. The first one tried is taken.
+    /// let tries = AtomicUsize::new(0);
+    /// let find_port = || async { 8080 + tries.fetch_add(1, Ordering::SeqCst) as u16 };
+    /// let is_free = |port| if port == 8080 { Err("port taken") } else { Ok(port) };
+    ///
+    /// // Step two: bind to it. The first bind fails.
+    /// let binds = AtomicUsize::new(0);
+    /// let bind = |port: &u16| {
+    ///     let port = *port;
+    ///     let bind = binds.fetch_add(1, Ordering::SeqCst);
+    ///     async move { if bind == 0 { Err("bind failed") } else { Ok(format!("bound to {}", port)) } }
+    /// };
+    ///
+    /// let success = Restartable::new(find_port, Some(Duration::from_secs(1)), is_free)
+    ///     .then_retry(bind, |bound| bound)
+    ///     .await
+    ///     .unwrap();
+    /// assert_eq!(success.value, "bound to 8081");
+    /// assert_eq!(success.restarts, 2);
+    /// # }
+    /// ```
+    pub async fn then_retry<NextFut, NextFactory, NextTest, U>(
+        self,
+        next_factory: NextFactory,
+        next_test: NextTest,
+    ) -> Outcome<U, E>
+    where
+        NextFut: Future,
+        NextFactory: Fn(&T) -> NextFut,
+        NextTest: Fn(NextFut::Output) -> Result<U, E>,
+    {
+        let timeout = self.timeout;
+        let deadline = self.deadline;
+        let first = self.await?;
+        let remaining = timeout.map(|timeout| timeout.saturating_sub(first.duration));
+        let value = first.value;
+        let mut next = Restartable::new(|| next_factory(&value), remaining, next_test);
+        if let Some(deadline) = deadline {
+            next = next.deadline(deadline);
+        }
+        match next.await {
+            Ok(second) => Ok(Success {
+                value: second.value,
+                duration: first.duration + second.duration,
+                restarts: first.restarts.saturating_add(second.restarts),
+                raw_output: None,
+                attempt_durations: [first.attempt_durations, second.attempt_durations].concat(),
+            }),
+            Err(failure) => Err(failure.with_earlier(first.restarts, first.duration)),
+        }
+    }
+}
+
+impl<E> Failure<E> {
+    /// Adds the restarts and elapsed time from an earlier loop to this failure's.
+    fn with_earlier(mut self, earlier: usize, earlier_elapsed: Duration) -> Self {
+        match &mut self {
+            Failure::Timeout {
+                restarts, elapsed, ..
+            }
+            | Failure::Err {
+                restarts, elapsed, ..
+            }
+            | Failure::NonRetryable {
+                restarts, elapsed, ..
+            } => {
+                *restarts = restarts.saturating_add(earlier);
+                *elapsed += earlier_elapsed;
+            }
+            Failure::Exhausted { restarts, .. }
+            | Failure::MaxRetries { restarts, .. }
+            | Failure::Cancelled { restarts }
+            | Failure::StopRequested { restarts }
+            | Failure::FactoryError { restarts, .. }
+            | Failure::SuccessRateUnmet { restarts } => {
+                *restarts = restarts.saturating_add(earlier)
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,129 @@
+//! A [`tower`] middleware which retries calls to a service. Requires the `use_tower` feature.
+//!
+//! [`RetryLayer`] wraps a service in [`Retry`], which retries each call under a [`RetryPolicy`],
+//! until the service's result passes a test, just like a [`Restartable`](crate::Restartable).
+//! Failed calls are retried after the policy's [`backoff`](crate::RetryPolicyBuilder::backoff),
+//! or straight away if it has none.
+//! The test decides which responses and errors are worth retrying, and what a passing call
+//! responds with.
+//!
+//! ```no_run
+//! use hyper::{Body, Client, Request, Response, Uri};
+//! use restartables::tower::RetryLayer;
+//! use restartables::{ExponentialBackoff, RetryPolicy};
+//! use std::time::Duration;
+//! use tower::{ServiceBuilder, ServiceExt};
+//!
+//! # async fn run() {
+//! // Retry connection errors and 5xx responses for up to five seconds, backing off between them.
+//! let policy = RetryPolicy::builder()
+//!     .timeout(Duration::from_secs(5))
+//!     .backoff(ExponentialBackoff {
+//!         base: Duration::from_millis(50),
+//!         factor: 2.0,
+//!         max: Some(Duration::from_secs(1)),
+//!     })
+//!     .build();
+//! let test = |result: hyper::Result<Response<Body>>| match result {
+//!     Ok(response) if response.status().is_server_error() => Err(response.status().to_string()),
+//!     Ok(response) => Ok(response),
+//!     Err(e) => Err(e.to_string()),
+//! };
+//! let service = ServiceBuilder::new()
+//!     .layer(RetryLayer::new(policy, test))
+//!     // Every attempt needs its own request, and hyper's requests can't be cloned, so retry the
+//!     // URI and build a request from it for each attempt.
+//!     .map_request(|uri: Uri| Request::get(uri).body(Body::empty()).unwrap())
+//!     .service(Client::new());
+//! let response = service.oneshot(Uri::from_static("http://localhost:8080/health")).await;
+//! # }
+//! ```
+
+use crate::{Failure, RetryPolicy};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service, ServiceExt};
+
+/// A [`Layer`] which wraps services in [`Retry`].
+#[derive(Debug, Clone)]
+pub struct RetryLayer<Test> {
+    policy: RetryPolicy,
+    test: Test,
+}
+
+impl<Test> RetryLayer<Test> {
+    /// Retries every call under `policy`, until the service's result passes `test`.
+    pub fn new(policy: RetryPolicy, test: Test) -> Self {
+        RetryLayer { policy, test }
+    }
+}
+
+impl<S, Test: Clone> Layer<S> for RetryLayer<Test> {
+    type Service = Retry<S, Test>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Retry {
+            inner,
+            policy: self.policy.clone(),
+            test: self.test.clone(),
+        }
+    }
+}
+
+/// A service which retries calls to the service it wraps, from [`RetryLayer`].
+///
+/// Every attempt clones the wrapped service and the request, and waits for that clone to be
+/// ready before calling it, so this service itself is always ready. A call responds with the
+/// value from the test, or fails with the [`Failure`] which ended its retries.
+#[derive(Debug, Clone)]
+pub struct Retry<S, Test> {
+    inner: S,
+    policy: RetryPolicy,
+    test: Test,
+}
+
+impl<S, Test> Retry<S, Test> {
+    /// Wraps `inner`, retrying every call under `policy`, until its result passes `test`.
+    pub fn new(inner: S, policy: RetryPolicy, test: Test) -> Self {
+        Retry {
+            inner,
+            policy,
+            test,
+        }
+    }
+
+    /// The wrapped service.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+}
+
+impl<S, Req, Test, T, E> Service<Req> for Retry<S, Test>
+where
+    S: Service<Req> + Clone + Send + 'static,
+    S::Future: Send,
+    Req: Clone + Send + 'static,
+    Test: Fn(Result<S::Response, S::Error>) -> Result<T, E> + Clone + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    type Response = T;
+    type Error = Failure<E>;
+    type Future = Pin<Box<dyn Future<Output = Result<T, Failure<E>>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        let inner = self.inner.clone();
+        let policy = self.policy.clone();
+        let test = self.test.clone();
+        Box::pin(async move {
+            let factory = move || inner.clone().oneshot(req.clone());
+            let success = policy.apply(factory, test).await?;
+            Ok(success.value)
+        })
+    }
+}
@@ -0,0 +1,132 @@
+use crate::Restartable;
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Like [`Restartable::new`], but the factory can fail to build a future, e.g. because a request
+/// it builds has a bad URL.
+///
+/// The first future is built straight away, and if that fails, this returns the factory's error
+/// instead of a `Restartable`. If building a later attempt fails, the `Restartable` gives up
+/// with [`Failure::FactoryError`](crate::Failure::FactoryError), since the factory would most
+/// likely fail the same way again. The factory's error type can differ from the test's, as long
+/// as the test's has a `From` impl for it, which converts the error for `FactoryError`. No
+/// attempt ran, so the factory's error isn't passed to [`inspect`](Restartable::inspect), kept
+/// by [`keep_recent_errors`](Restartable::keep_recent_errors), counted towards a success rate,
+/// or reported to a metrics sink as an attempt's result.
+///
+/// ```
+/// use restartables::{try_factory, Failure};
+/// use std::num::ParseIntError;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum ConnectError {
+///     BadConfig(ParseIntError),
+///     Refused(u16),
+/// }
+///
+/// impl From<ParseIntError> for ConnectError {
+///     fn from(e: ParseIntError) -> Self {
+///         ConnectError::BadConfig(e)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// // Each attempt reads the port to connect to from config, which someone has just broken.
+/// let configs = ["8080", "80a"];
+/// let reads = AtomicUsize::new(0);
+/// let connect = || {
+///     let port = configs[reads.fetch_add(1, Ordering::SeqCst)].parse::<u16>()?;
+///     Ok::<_, ParseIntError>(async move { port })
+/// };
+/// let refused = |port| Err::<(), _>(ConnectError::Refused(port));
+/// match try_factory(connect, None, refused).unwrap().await {
+///     Err(Failure::FactoryError { error, restarts }) => {
+///         assert!(matches!(error, ConnectError::BadConfig(_)));
+///         assert_eq!(restarts, 1);
+///     }
+///     other => panic!("expected a factory error, got {:?}", other),
+/// }
+///
+/// // If the first future can't be built, there's no Restartable at all, and the factory's own
+/// // error is returned.
+/// let broken = || "80a".parse::<u16>().map(|port| async move { port });
+/// match try_factory(broken, None, |port| Ok::<_, ConnectError>(port)) {
+///     Err(error) => assert_eq!(error.to_string(), "invalid digit found in string"),
+///     Ok(_) => panic!("expected the first future to fail"),
+/// }
+/// # }
+/// ```
+#[allow(clippy::type_complexity)]
+pub fn try_factory<Fut, Factory, Test, T, E, FErr>(
+    factory: Factory,
+    timeout: Option<Duration>,
+    test: Test,
+) -> Result<
+    Restartable<
+        TryAttempt<Fut, E>,
+        impl Fn(Result<Fut::Output, E>) -> Result<T, E>,
+        impl Fn() -> TryAttempt<Fut, E>,
+        T,
+        E,
+    >,
+    FErr,
+>
+where
+    Fut: Future,
+    Factory: Fn() -> Result<Fut, FErr>,
+    Test: Fn(Fut::Output) -> Result<T, E>,
+    E: From<FErr>,
+{
+    // Build the first future here, so its error can be returned, and hand it to the first attempt.
+    let first = Mutex::new(Some(factory()?));
+    let factory = move || {
+        let first = first.lock().unwrap_or_else(PoisonError::into_inner).take();
+        let built = match first {
+            Some(fut) => Ok(fut),
+            None => factory(),
+        };
+        TryAttempt {
+            inner: match built {
+                Ok(fut) => Inner::Built(fut),
+                Err(error) => Inner::Failed(Some(E::from(error))),
+            },
+        }
+    };
+    let test = move |output: Result<Fut::Output, E>| output.and_then(&test);
+    let mut restartable = Restartable::new(factory, timeout, test);
+    restartable.factory_failed = |output| output.is_err();
+    Ok(restartable)
+}
+
+/// One attempt from [`try_factory`]: the future the factory built, or the error it failed with.
+#[pin_project]
+pub struct TryAttempt<Fut, E> {
+    #[pin]
+    inner: Inner<Fut, E>,
+}
+
+#[pin_project(project = InnerProj)]
+enum Inner<Fut, E> {
+    Built(#[pin] Fut),
+    Failed(Option<E>),
+}
+
+impl<Fut: Future, E> Future for TryAttempt<Fut, E> {
+    type Output = Result<Fut::Output, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match self.project().inner.project() {
+            InnerProj::Built(fut) => fut.poll(cx).map(Ok),
+            InnerProj::Failed(error) => {
+                let error = error.take().expect("TryAttempt polled after completion");
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{Resolve, Restartable};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// A Restartable configuration which can't work as intended, from [`Restartable::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigError {
+    /// The attempt limit is zero, which still allows one attempt
+    ZeroMaxAttempts,
+    /// The limit on polls per attempt is zero, so every attempt is abandoned after one poll
+    ZeroMaxPendingPolls,
+    /// The timeout is zero, so there's only ever one attempt
+    ZeroTimeout,
+    /// The minimum interval between attempts is at least the timeout, so there's never time to
+    /// retry
+    IntervalExceedsTimeout {
+        /// From [`Restartable::min_retry_interval`]
+        interval: Duration,
+        /// The overall timeout
+        timeout: Duration,
+    },
+    /// The success rate's window holds more attempts than are allowed, so it can never fill
+    SuccessWindowExceedsAttempts {
+        /// From [`Restartable::require_success_rate`]
+        window: usize,
+        /// The attempt limit
+        max_attempts: usize,
+    },
+    /// Nothing but a passing attempt can stop the loop, and that hasn't been acknowledged with
+    /// [`Restartable::allow_unbounded`]
+    Unbounded,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroMaxAttempts => write!(f, "max attempts is zero"),
+            ConfigError::ZeroMaxPendingPolls => write!(f, "max pending polls is zero"),
+            ConfigError::ZeroTimeout => write!(f, "timeout is zero"),
+            ConfigError::IntervalExceedsTimeout { interval, timeout } => write!(
+                f,
+                "min retry interval of {:?} leaves no time to retry within the timeout of {:?}",
+                interval, timeout
+            ),
+            ConfigError::SuccessWindowExceedsAttempts {
+                window,
+                max_attempts,
+            } => write!(
+                f,
+                "success rate window of {} attempts can't fill within {} attempts",
+                window, max_attempts
+            ),
+            ConfigError::Unbounded => write!(
+                f,
+                "nothing stops the loop retrying forever, and allow_unbounded wasn't called"
+            ),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl<Fut, Test, Factory, T, E, Raw, Ctx> Restartable<Fut, Test, Factory, T, E, Raw, Ctx>
+where
+    Fut: Future,
+    Factory: Fn() -> Fut,
+    Test: Resolve<Fut::Output, Ok = T, Err = E>,
+{
+    /// Checks this Restartable's configuration for settings which can't work as intended, before
+    /// it runs.
+    ///
+    /// A misconfigured loop usually only shows itself after it's been awaited, e.g. by never
+    /// resolving. This catches those mistakes straight away instead. It's optional: a Restartable
+    /// which fails validation still runs, exactly as configured. Only the first problem found is
+    /// returned.
+    ///
+    /// ```
+    /// use restartables::{ConfigError, Restartable};
+    /// use std::time::Duration;
+    ///
+    /// let forever = Restartable::new(|| async { 1 }, None, |n| Err::<(), _>(n));
+    /// assert_eq!(forever.validate(), Err(ConfigError::Unbounded));
+    ///
+    /// let too_slow = Restartable::new(|| async { 1 }, Some(Duration::from_secs(1)), |n| Err::<(), _>(n))
+    ///     .min_retry_interval(Duration::from_secs(5));
+    /// assert!(matches!(too_slow.validate(), Err(ConfigError::IntervalExceedsTimeout { .. })));
+    /// ```
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.max_attempts == Some(0) {
+            return Err(ConfigError::ZeroMaxAttempts);
+        }
+        if self.max_pending_polls == Some(0) {
+            return Err(ConfigError::ZeroMaxPendingPolls);
+        }
+        if let Some(timeout) = self.timeout {
+            if timeout.is_zero() {
+                return Err(ConfigError::ZeroTimeout);
+            }
+            if let Some(interval) = self.min_retry_interval.filter(|&i| i >= timeout) {
+                return Err(ConfigError::IntervalExceedsTimeout { interval, timeout });
+            }
+        }
+        if let (Some(rate), Some(max_attempts)) = (self.success_rate.as_ref(), self.max_attempts) {
+            if rate.window() > max_attempts {
+                return Err(ConfigError::SuccessWindowExceedsAttempts {
+                    window: rate.window(),
+                    max_attempts,
+                });
+            }
+        }
+        if self.is_unbounded() {
+            return Err(ConfigError::Unbounded);
+        }
+        Ok(())
+    }
+
+    /// Like [`validate`](Restartable::validate), but panics if the configuration is invalid, so
+    /// it can be called at the end of a chain of builder methods.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the [`ConfigError`]'s message if the configuration is invalid.
+    ///
+    /// ```should_panic
+    /// use restartables::Restartable;
+    ///
+    /// let retrying = Restartable::retry_n(|| async { 1 }, |n| Err::<(), _>(n), 0).build_checked();
+    /// ```
+    pub fn build_checked(self) -> Self {
+        if let Err(error) = self.validate() {
+            panic!("invalid Restartable configuration: {}", error);
+        }
+        self
+    }
+
+    /// Whether nothing but a passing attempt can stop this, and that hasn't been acknowledged.
+    pub(crate) fn is_unbounded(&self) -> bool {
+        !self.allow_unbounded
+            && self.timeout.is_none()
+            && self.deadline.is_none()
+            && self.max_attempts.is_none()
+            && self.repeated.is_none()
+            && self.cancelled.is_none()
+            && self.until.is_none()
+            && !self
+                .backoff
+                .as_ref()
+                .is_some_and(|backoff| backoff.can_stop())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Delays;
+
+    #[test]
+    fn sensible_configs_are_valid() {
+        let factory = || std::future::ready(1);
+        let timed = Restartable::new(factory, Some(Duration::from_secs(1)), Ok::<_, ()>)
+            .min_retry_interval(Duration::from_millis(10));
+        assert_eq!(timed.validate(), Ok(()));
+        let limited = Restartable::retry_n(factory, Ok::<_, ()>, 5).require_success_rate(5, 0.8);
+        assert_eq!(limited.validate(), Ok(()));
+        let acknowledged = Restartable::new(factory, None, Ok::<_, ()>).allow_unbounded();
+        assert_eq!(acknowledged.validate(), Ok(()));
+    }
+
+    #[test]
+    fn only_a_backoff_which_can_stop_bounds_the_loop() {
+        let factory = || std::future::ready(1);
+        let forever = Restartable::new(factory, None, Ok::<_, ()>).backoff(Duration::from_secs(1));
+        assert_eq!(forever.validate(), Err(ConfigError::Unbounded));
+        let schedule = vec![Duration::from_secs(1); 3].into_iter();
+        let scheduled = Restartable::new(factory, None, Ok::<_, ()>).backoff(Delays(schedule));
+        assert_eq!(scheduled.validate(), Ok(()));
+    }
+
+    #[test]
+    fn a_success_window_bigger_than_the_attempt_limit_is_invalid() {
+        let retrying = Restartable::retry_n(|| std::future::ready(1), Ok::<_, ()>, 3)
+            .require_success_rate(5, 0.8);
+        assert_eq!(
+            retrying.validate(),
+            Err(ConfigError::SuccessWindowExceedsAttempts {
+                window: 5,
+                max_attempts: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::future::Future;
+use std::pin::Pin;
+#[cfg(not(feature = "use_tokio"))]
+use std::task::{Context, Poll};
+
+/// A pending yield back to the executor, for [`Restartable::yield_between_attempts`].
+///
+/// [`Restartable::yield_between_attempts`]: crate::Restartable::yield_between_attempts
+pub(crate) type Yield = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+
+/// Yields with [`tokio::task::yield_now`], which lets every other ready task run first.
+#[cfg(feature = "use_tokio")]
+pub(crate) fn yield_now() -> Yield {
+    Box::pin(tokio::task::yield_now())
+}
+
+/// Yields by waking the task and returning `Poll::Pending` once, which works on any executor.
+#[cfg(not(feature = "use_tokio"))]
+pub(crate) fn yield_now() -> Yield {
+    Box::pin(YieldNow { yielded: false })
+}
+
+#[cfg(not(feature = "use_tokio"))]
+struct YieldNow {
+    yielded: bool,
+}
+
+#[cfg(not(feature = "use_tokio"))]
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+        self.yielded = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,35 @@
+//! Backoff on async-std, with and without tokio's timer also enabled. Run with:
+//!
+//! ```bash
+//! cargo test --test async_std --features use_async_std
+//! cargo test --test async_std --features use_async_std,use_tokio
+//! ```
+#![cfg(feature = "use_async_std")]
+
+use restartables::Restartable;
+use std::time::{Duration, Instant};
+
+#[test]
+fn backoff_sleeps_on_async_std() {
+    let start = Instant::now();
+    let outcome = async_std::task::block_on(
+        Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 3).backoff(Duration::from_millis(20)),
+    );
+    assert!(outcome.is_err());
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
+
+/// Another crate turning on `use_tokio` mustn't break backoff outside a tokio runtime.
+#[cfg(feature = "use_tokio")]
+#[test]
+fn backoff_sleeps_on_async_std_even_with_tokio_enabled() {
+    let start = Instant::now();
+    let outcome = async_std::task::block_on(async {
+        assert!(tokio::runtime::Handle::try_current().is_err());
+        Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 3)
+            .backoff(Duration::from_millis(20))
+            .await
+    });
+    assert!(outcome.is_err());
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
@@ -0,0 +1,45 @@
+//! Compile-time checks that `Restartable` is `Send` and `Sync` exactly when its parts are.
+
+use restartables::{Failure, Restartable, RetryPolicy, Success};
+use static_assertions::{assert_impl_all, assert_not_impl_any};
+use std::cell::Cell;
+use std::future::Ready;
+use std::rc::Rc;
+
+/// A Restartable whose parts are all `Send + Sync`, except where a test swaps one out.
+type Retrying<Out, T = u8, E = u8, Raw = (), Ctx = ()> =
+    Restartable<Ready<Out>, fn(Out) -> Result<T, E>, fn() -> Ready<Out>, T, E, Raw, Ctx>;
+
+assert_impl_all!(Retrying<u8>: Send, Sync, Unpin);
+
+// The error is never held between polls, so it doesn't matter.
+assert_impl_all!(Retrying<u8, u8, Rc<u8>>: Send, Sync);
+
+// Anything which is held does.
+assert_not_impl_any!(Retrying<Rc<u8>>: Send, Sync);
+assert_not_impl_any!(Retrying<u8, Rc<u8>>: Send, Sync);
+assert_not_impl_any!(Retrying<u8, u8, u8, Rc<u8>>: Send, Sync);
+assert_not_impl_any!(Retrying<u8, u8, u8, (), Rc<u8>>: Send, Sync);
+
+// A context which is `Send` but not `Sync` makes the Restartable the same.
+assert_impl_all!(Retrying<u8, u8, u8, (), Cell<u8>>: Send);
+assert_not_impl_any!(Retrying<u8, u8, u8, (), Cell<u8>>: Sync);
+
+assert_impl_all!(Success<u8>: Send, Sync);
+assert_impl_all!(Failure<u8>: Send, Sync);
+assert_impl_all!(RetryPolicy: Send, Sync);
+
+#[tokio::test]
+async fn restartables_with_every_hook_can_be_spawned() {
+    let retrying = Restartable::new(|| async { 1 }, None, |n: u8| Ok::<_, Rc<u8>>(n))
+        .inspect(|_| {})
+        .gate_next_attempt(|| async {})
+        .attempt_timeout_fn(|_| std::time::Duration::from_secs(1))
+        .with_context(Cell::new(0))
+        .allow_unbounded();
+    // A non-`Send` error is fine, as long as it's dropped before crossing threads.
+    let restarts = tokio::spawn(async move { retrying.await.map(|s| s.restarts).ok() })
+        .await
+        .unwrap();
+    assert_eq!(restarts, Some(0));
+}
@@ -0,0 +1,54 @@
+use restartables::{Restartable, Spawner};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+struct Tokio;
+
+impl Spawner for Tokio {
+    fn spawn(&self, task: Pin<Box<dyn Future<Output = ()> + Send + 'static>>) {
+        tokio::spawn(task);
+    }
+}
+
+#[tokio::test]
+async fn background_loops_keep_the_attempt_limit() {
+    let builds = Arc::new(AtomicUsize::new(0));
+    let factory = {
+        let builds = builds.clone();
+        move || {
+            let n = builds.fetch_add(1, Ordering::SeqCst);
+            async move { n }
+        }
+    };
+    // Only the very first attempt passes, so the background loop fails, and would restart until
+    // the end of time without the limit.
+    let test = |n: usize| if n == 0 { Ok(n) } else { Err(n) };
+    Restartable::retry_n(factory, test, 2)
+        .return_first_and_continue(Tokio, |_| true)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(builds.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn background_loops_wait_the_min_retry_interval_between_them() {
+    let values = Arc::new(AtomicUsize::new(0));
+    let seen = values.clone();
+    Restartable::new(|| async { 1 }, None, Ok::<_, ()>)
+        .allow_unbounded()
+        .min_retry_interval(Duration::from_millis(20))
+        .return_first_and_continue(Tokio, move |_| {
+            seen.fetch_add(1, Ordering::SeqCst);
+            true
+        })
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    // Roughly one value every 20ms, rather than as many as the runtime can spin through.
+    let values = values.load(Ordering::SeqCst);
+    assert!((1..=10).contains(&values), "{} values", values);
+}
@@ -0,0 +1,200 @@
+use restartables::{
+    Backoff, Delays, ExponentialBackoff, Failure, FixedInterval, Restartable, TimeoutScope,
+};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// A backoff which records the restart counts it's asked about.
+#[derive(Clone, Default)]
+struct Recording(Arc<Mutex<Vec<usize>>>);
+
+impl Backoff for Recording {
+    fn delay(&mut self, restarts: usize) -> Duration {
+        self.0.lock().unwrap().push(restarts);
+        Duration::from_millis(1)
+    }
+}
+
+#[tokio::test]
+async fn backoff_is_asked_after_every_failed_attempt() {
+    let backoff = Recording::default();
+    let outcome = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 4)
+        .backoff(backoff.clone())
+        .await;
+    assert!(matches!(
+        outcome,
+        Err(Failure::Exhausted { restarts: 3, .. })
+    ));
+    // The last failure ends the loop, so there's nothing to back off before.
+    assert_eq!(*backoff.0.lock().unwrap(), [0, 1, 2]);
+}
+
+#[tokio::test]
+async fn backoff_is_asked_after_passes_which_dont_meet_the_success_rate() {
+    let backoff = Recording::default();
+    let success = Restartable::retry_n(|| async { 1 }, Ok::<i32, ()>, 10)
+        .require_success_rate(3, 1.0)
+        .backoff(backoff.clone())
+        .await
+        .unwrap();
+    assert_eq!(success.restarts, 2);
+    assert_eq!(*backoff.0.lock().unwrap(), [0, 1]);
+}
+
+#[tokio::test]
+async fn backoff_never_waits_past_the_timeout() {
+    let start = Instant::now();
+    let outcome = Restartable::with_backoff(
+        || async { 1 },
+        Some(Duration::from_millis(50)),
+        Err::<(), i32>,
+        Duration::from_secs(10),
+    )
+    .await;
+    assert!(outcome.is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn exponential_backoff_saturates_instead_of_overflowing() {
+    let mut backoff = ExponentialBackoff {
+        base: Duration::from_secs(1),
+        factor: 10.0,
+        max: None,
+    };
+    assert_eq!(backoff.delay(2), Duration::from_secs(100));
+    assert_eq!(backoff.delay(usize::MAX), Duration::MAX);
+}
+
+#[tokio::test]
+async fn exponential_backoff_spaces_out_attempts() {
+    let start = Instant::now();
+    let backoff = ExponentialBackoff {
+        base: Duration::from_millis(5),
+        factor: 2.0,
+        max: Some(Duration::from_millis(10)),
+    };
+    let outcome = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 4)
+        .backoff(backoff)
+        .await;
+    assert!(outcome.is_err());
+    // 5ms, then 10ms, then 20ms capped to 10ms.
+    assert!(start.elapsed() >= Duration::from_millis(25));
+}
+
+#[tokio::test]
+async fn backoff_can_stop_after_an_abandoned_attempt() {
+    let outcome = Restartable::new(std::future::pending::<i32>, None, Ok::<i32, ()>)
+        .max_pending_polls(2)
+        .backoff(Delays(std::iter::once(Duration::ZERO)))
+        .await;
+    assert!(matches!(
+        outcome,
+        Err(Failure::Timeout {
+            restarts: 1,
+            scope: TimeoutScope::Attempt,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn worst_case_totals_add_up_the_delays_between_attempts() {
+    let fixed = FixedInterval(Duration::from_secs(1));
+    assert_eq!(fixed.worst_case_total(Some(1)), Some(Duration::ZERO));
+    assert_eq!(
+        fixed.worst_case_total(Some(5)),
+        Some(Duration::from_secs(4))
+    );
+    assert_eq!(fixed.worst_case_total(None), None);
+
+    // A schedule stops before the attempts run out, or the other way round.
+    let schedule = Delays(vec![Duration::from_secs(1); 3].into_iter());
+    assert_eq!(
+        schedule.worst_case_total(Some(10)),
+        Some(Duration::from_secs(3))
+    );
+    assert_eq!(
+        schedule.worst_case_total(Some(2)),
+        Some(Duration::from_secs(1))
+    );
+
+    // An endless schedule needs an attempt limit.
+    let endless = Delays(std::iter::repeat(Duration::from_secs(1)));
+    assert!(!endless.can_stop());
+    assert_eq!(endless.worst_case_total(None), None);
+    assert_eq!(
+        endless.worst_case_total(Some(3)),
+        Some(Duration::from_secs(2))
+    );
+}
+
+#[tokio::test]
+async fn a_run_of_passes_resets_the_backoff() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Two failures, two passes, then nothing but failures.
+    let attempts = AtomicUsize::new(0);
+    let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    let test = |n: usize| if n == 2 || n == 3 { Ok(n) } else { Err(n) };
+    let backoff = Recording::default();
+    let outcome = Restartable::retry_n(factory, test, 7)
+        .require_success_rate(10, 1.0)
+        .backoff(backoff.clone())
+        .reset_backoff_after_success(2)
+        .await;
+    assert!(matches!(outcome, Err(Failure::Exhausted { error: 6, .. })));
+    assert_eq!(*backoff.0.lock().unwrap(), [0, 1, 2, 3, 0, 1]);
+}
+
+#[tokio::test]
+async fn backoff_for_picks_the_backoff_after_test_failures() {
+    let fallback = Recording::default();
+    let per_error = Recording::default();
+    let picked = per_error.clone();
+    let outcome = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 4)
+        .backoff(fallback.clone())
+        .backoff_for(move |error| {
+            assert_eq!(*error, 1);
+            picked.clone()
+        })
+        .await;
+    assert!(outcome.is_err());
+    assert_eq!(*per_error.0.lock().unwrap(), [0, 1, 2]);
+    assert!(fallback.0.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn repeated_errors_are_checked_before_the_backoff() {
+    // The schedule only has room for one restart, but the second identical error should still
+    // be reported as a repeat rather than as the backoff running out.
+    let outcome = Restartable::new(
+        || async { 1 },
+        Some(Duration::from_secs(60)),
+        Err::<(), i32>,
+    )
+    .backoff(Delays::new([Duration::ZERO]))
+    .give_up_on_repeated_error(2)
+    .await;
+    assert!(matches!(
+        outcome,
+        Err(Failure::Err {
+            error: 1,
+            restarts: 1,
+            ..
+        })
+    ));
+
+    // Giving up doesn't take a delay from the backoff.
+    let backoff = Recording::default();
+    let outcome = Restartable::new(
+        || async { 1 },
+        Some(Duration::from_secs(60)),
+        Err::<(), i32>,
+    )
+    .backoff(backoff.clone())
+    .give_up_on_repeated_error(3)
+    .await;
+    assert!(matches!(outcome, Err(Failure::Err { restarts: 2, .. })));
+    assert_eq!(*backoff.0.lock().unwrap(), [0, 1]);
+}
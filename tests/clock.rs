@@ -0,0 +1,158 @@
+#![cfg(feature = "testing")]
+
+use restartables::testing::ManualClock;
+use restartables::{AttemptLog, Failure, Restartable, TimeoutScope};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Never resolves, but takes 1ms of fake time for every poll.
+struct Ticking {
+    clock: ManualClock,
+    polls: Arc<AtomicUsize>,
+}
+
+impl Future for Ticking {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        self.clock.advance(Duration::from_millis(1));
+        self.polls.fetch_add(1, Ordering::SeqCst);
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[tokio::test]
+async fn timeout_fires_as_soon_as_the_clock_passes_it() {
+    let clock = ManualClock::new();
+    let polls = Arc::new(AtomicUsize::new(0));
+    let factory = {
+        let clock = clock.clone();
+        let polls = polls.clone();
+        move || Ticking {
+            clock: clock.clone(),
+            polls: polls.clone(),
+        }
+    };
+    let outcome = Restartable::new(factory, Some(Duration::from_millis(10)), Ok::<(), ()>)
+        .clock(clock)
+        .await;
+    match outcome {
+        Err(Failure::Timeout {
+            restarts: 0,
+            scope: TimeoutScope::Overall,
+            elapsed,
+        }) => assert_eq!(elapsed, Duration::from_millis(11)),
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    assert_eq!(polls.load(Ordering::SeqCst), 11);
+}
+
+#[tokio::test]
+async fn attempt_timeout_uses_the_clock_too() {
+    let clock = ManualClock::new();
+    let polls = Arc::new(AtomicUsize::new(0));
+    let factory = {
+        let clock = clock.clone();
+        let polls = polls.clone();
+        move || Ticking {
+            clock: clock.clone(),
+            polls: polls.clone(),
+        }
+    };
+    let outcome = Restartable::new(factory, Some(Duration::from_millis(10)), Ok::<(), ()>)
+        .attempt_timeout_fn(|_| Duration::from_millis(3))
+        .clock(clock)
+        .await;
+    // Each attempt is abandoned on its 4th poll, so the 11th poll is the third attempt's 3rd.
+    assert!(matches!(outcome, Err(Failure::Timeout { restarts: 2, .. })));
+    assert_eq!(polls.load(Ordering::SeqCst), 11);
+}
+
+#[tokio::test]
+async fn success_durations_come_from_the_clock() {
+    let clock = ManualClock::new();
+    let attempts = AtomicUsize::new(0);
+    let factory = || {
+        let clock = clock.clone();
+        let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+        async move {
+            clock.advance(Duration::from_millis(5));
+            attempt
+        }
+    };
+    let success = Restartable::new(factory, Some(Duration::from_secs(1)), |n| {
+        if n == 2 {
+            Ok(n)
+        } else {
+            Err(n)
+        }
+    })
+    .clock(clock.clone())
+    .await
+    .unwrap();
+    // Exactly the fake time which passed, however long the attempts really took.
+    assert_eq!(success.duration, Duration::from_millis(15));
+    assert_eq!(
+        success.attempt_durations,
+        [Duration::from_millis(5); 3].to_vec()
+    );
+}
+
+#[tokio::test]
+async fn attempt_logs_take_durations_from_the_clock() {
+    let clock = ManualClock::new();
+    let polls = Arc::new(AtomicUsize::new(0));
+    let factory = {
+        let clock = clock.clone();
+        move || Ticking {
+            clock: clock.clone(),
+            polls: polls.clone(),
+        }
+    };
+    let log = AttemptLog::with_capacity(10);
+    let outcome = Restartable::new(factory, Some(Duration::from_millis(10)), Ok::<(), &str>)
+        .attempt_timeout_fn(|_| Duration::from_millis(3))
+        .min_retry_interval(Duration::from_millis(20))
+        .metrics(log.clone())
+        .clock(clock)
+        .await;
+    assert!(outcome.is_err());
+    // Every attempt is closed when it's abandoned, so the real time spent waiting between them
+    // isn't counted, and the last one is closed when the whole loop times out.
+    let records = log.records();
+    let durations: Vec<_> = records.iter().map(|record| record.duration).collect();
+    assert_eq!(durations, [4, 4, 3].map(Duration::from_millis));
+    assert!(records.iter().all(|record| record.result.is_err()));
+}
+
+#[tokio::test]
+async fn reports_take_their_times_from_the_clock() {
+    let clock = ManualClock::new();
+    let polls = Arc::new(AtomicUsize::new(0));
+    let factory = {
+        let clock = clock.clone();
+        move || Ticking {
+            clock: clock.clone(),
+            polls: polls.clone(),
+        }
+    };
+    let (outcome, report) =
+        Restartable::new(factory, Some(Duration::from_millis(10)), Ok::<(), &str>)
+            .attempt_timeout_fn(|_| Duration::from_millis(3))
+            .min_retry_interval(Duration::from_millis(20))
+            .clock(clock)
+            .with_full_report(2)
+            .await;
+    assert!(outcome.is_err());
+    assert_eq!(report.attempts, 3);
+    assert_eq!(report.elapsed, Duration::from_millis(11));
+    // The fake clock doesn't move while the loop really waits between attempts.
+    assert_eq!(report.waiting, Duration::ZERO);
+    let indices: Vec<_> = report.records.iter().map(|record| record.index).collect();
+    assert_eq!(indices, [1, 2]);
+}
@@ -0,0 +1,108 @@
+//! Guards against feature-gating regressions.
+//!
+//! `core_api_is_feature_independent` runs with whatever features `cargo test` was given, and checks
+//! the core API doesn't change shape. `every_feature_combination_builds` checks every subset of
+//! the features in Cargo.toml. It runs `cargo check` once per subset, so it's ignored by default:
+//!
+//! ```bash
+//! cargo test --test features -- --ignored
+//! ```
+
+use restartables::{CompletionReason, Failure, OutcomeReason, Restartable, StopReason};
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+
+#[tokio::test]
+async fn core_api_is_feature_independent() {
+    let outcome = Restartable::new(
+        || async { 3 },
+        Some(Duration::from_millis(1)),
+        |n: u8| if n > 5 { Ok(n) } else { Err(n) },
+    )
+    .await;
+    match &outcome {
+        Err(Failure::Err { error, .. }) => assert_eq!(*error, 3),
+        other => panic!("expected the test's error, got {:?}", other),
+    }
+    assert_eq!(outcome.reason(), CompletionReason::TimedOut);
+
+    let outcome = Restartable::retry_n(|| async { 4 }, |n: u8| Ok::<_, ()>(n), 1).await;
+    assert_eq!(outcome.reason(), CompletionReason::FirstTry);
+
+    let failure = Restartable::retry_n(|| async { 1 }, |n: u8| Err::<(), _>(n), 1)
+        .await
+        .unwrap_err();
+    assert_eq!(failure.reason(), StopReason::Exhausted);
+}
+
+#[cfg(feature = "use_tokio")]
+#[test]
+fn tokio_spawner_is_a_spawner() {
+    fn assert_spawner<S: restartables::Spawner>(_: S) {}
+    assert_spawner(restartables::TokioSpawner);
+}
+
+#[cfg(feature = "testing")]
+#[tokio::test]
+async fn chaos_factory_feeds_restartable() {
+    let chaos = restartables::testing::ChaosFactory::new(|| async { 1 }).fail_first(1);
+    let success = Restartable::retry_n(|| chaos.attempt(), |output| output, 2)
+        .await
+        .unwrap();
+    assert_eq!(success.restarts, 1);
+}
+
+#[test]
+#[ignore]
+fn every_feature_combination_builds() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let features = features(&manifest_dir.join("Cargo.toml"));
+    assert!(!features.is_empty(), "no features found in Cargo.toml");
+
+    // A separate target dir, so this doesn't wait on the lock held by the outer `cargo test`.
+    let target_dir = manifest_dir.join("target").join("feature-powerset");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_owned());
+
+    let mut broken = Vec::new();
+    for mask in 0..1u32 << features.len() {
+        let subset: Vec<&str> = features
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| mask & (1 << i) != 0)
+            .map(|(_, feature)| feature.as_str())
+            .collect();
+        let status = Command::new(&cargo)
+            .current_dir(manifest_dir)
+            .args(["check", "--quiet", "--lib", "--no-default-features"])
+            .arg("--features")
+            .arg(subset.join(","))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .expect("couldn't run cargo");
+        if !status.success() {
+            broken.push(subset.join(","));
+        }
+    }
+    assert!(
+        broken.is_empty(),
+        "these feature combinations don't build: {:?}",
+        broken
+    );
+}
+
+/// Reads the feature names from the `[features]` table of a Cargo.toml.
+fn features(manifest: &Path) -> Vec<String> {
+    let manifest = std::fs::read_to_string(manifest).expect("couldn't read Cargo.toml");
+    manifest
+        .lines()
+        .skip_while(|line| line.trim() != "[features]")
+        .skip(1)
+        .take_while(|line| !line.trim_start().starts_with('['))
+        .filter_map(|line| line.split('=').next())
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && !name.starts_with('#') && *name != "default")
+        .map(str::to_owned)
+        .collect()
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "global_defaults")]
+
+use restartables::global::{global_defaults, set_global_defaults};
+use restartables::{Failure, Outcome, Restartable, RetryPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+fn always_fails(n: u8) -> Result<u8, u8> {
+    Err(n)
+}
+
+fn exhausted_after(outcome: Outcome<u8, u8>) -> usize {
+    match outcome {
+        Err(Failure::Exhausted { restarts, .. }) => restarts,
+        other => panic!("expected exhaustion, got {:?}", other),
+    }
+}
+
+// The defaults are global, so every check runs in this one test, one after another.
+#[tokio::test]
+async fn explicit_settings_beat_global_defaults_which_beat_built_in_ones() {
+    assert!(global_defaults().is_none());
+    set_global_defaults(RetryPolicy::builder().max_attempts(3).build());
+
+    // Constructors inherit the global defaults.
+    let retrying = Restartable::new(|| async { 1 }, None, always_fails);
+    assert_eq!(exhausted_after(retrying.await), 2);
+
+    // An explicit limit beats the global one.
+    let policy = RetryPolicy::builder().max_attempts(5).build();
+    assert_eq!(
+        exhausted_after(policy.apply(|| async { 1 }, always_fails).await),
+        4
+    );
+    let retrying = Restartable::retry_n(|| async { 1 }, always_fails, 4);
+    assert_eq!(exhausted_after(retrying.await), 3);
+
+    // Passes once 30ms have gone by.
+    set_global_defaults(
+        RetryPolicy::builder()
+            .timeout(Duration::from_millis(10))
+            .build(),
+    );
+    let start = Instant::now();
+    let factory = || async {
+        tokio::task::yield_now().await;
+        1
+    };
+    let eventually = |n: u8| {
+        if start.elapsed() > Duration::from_millis(30) {
+            Ok(n)
+        } else {
+            Err(n)
+        }
+    };
+
+    // An explicit timeout beats the global one.
+    let retrying = Restartable::new(factory, None, eventually);
+    assert!(retrying.await.is_err());
+    let retrying = Restartable::new(factory, Some(Duration::from_secs(5)), eventually);
+    assert_eq!(retrying.await.unwrap().value, 1);
+
+    // A default per-attempt timeout applies from the first attempt, which would otherwise never
+    // finish.
+    set_global_defaults(
+        RetryPolicy::builder()
+            .attempt_timeout(Duration::from_millis(5))
+            .build(),
+    );
+    let attempts = AtomicUsize::new(0);
+    let stuck_once = || async {
+        if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            loop {
+                tokio::task::yield_now().await;
+            }
+        }
+        1
+    };
+    let retrying = Restartable::new(stuck_once, None, |n: u8| Ok::<_, u8>(n));
+    assert_eq!(retrying.await.unwrap().restarts, 1);
+    attempts.store(0, Ordering::SeqCst);
+    let retrying = Restartable::retry_n(stuck_once, |n: u8| Ok::<_, u8>(n), 3);
+    assert_eq!(retrying.await.unwrap().restarts, 1);
+
+    // Resetting the defaults brings back the built-in ones, i.e. no limits.
+    set_global_defaults(RetryPolicy::default());
+    let start = Instant::now();
+    let eventually = |n: u8| {
+        if start.elapsed() > Duration::from_millis(30) {
+            Ok(n)
+        } else {
+            Err(n)
+        }
+    };
+    let retrying = Restartable::new(factory, None, eventually);
+    assert_eq!(retrying.await.unwrap().value, 1);
+}
@@ -0,0 +1,53 @@
+#![cfg(feature = "use_tonic")]
+
+use restartables::{grpc, Failure};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tonic::{Code, Response, Status};
+
+/// A call which fails with each of `statuses` in turn, then succeeds with how many calls it took.
+fn flaky(
+    statuses: Vec<Status>,
+    calls: &AtomicUsize,
+) -> impl Fn() -> std::future::Ready<Result<Response<usize>, Status>> + '_ {
+    move || {
+        let call = calls.fetch_add(1, Ordering::SeqCst);
+        std::future::ready(match statuses.get(call) {
+            Some(status) => Err(Status::new(status.code(), status.message())),
+            None => Ok(Response::new(call + 1)),
+        })
+    }
+}
+
+#[tokio::test]
+async fn retryable_statuses_are_retried() {
+    let calls = AtomicUsize::new(0);
+    let statuses = vec![
+        Status::unavailable("connection refused"),
+        Status::resource_exhausted("over quota"),
+    ];
+    let success = grpc::execute(flaky(statuses, &calls), Some(Duration::from_secs(1)))
+        .await
+        .unwrap();
+    assert_eq!(success.value.into_inner(), 3);
+    assert_eq!(success.restarts, 2);
+}
+
+#[tokio::test]
+async fn other_statuses_give_up_straight_away() {
+    let calls = AtomicUsize::new(0);
+    let statuses = vec![
+        Status::unavailable("connection refused"),
+        Status::invalid_argument("name is empty"),
+    ];
+    match grpc::execute(flaky(statuses, &calls), Some(Duration::from_secs(1))).await {
+        Err(Failure::NonRetryable {
+            error, restarts, ..
+        }) => {
+            assert_eq!(error.code(), Code::InvalidArgument);
+            assert_eq!(restarts, 1);
+        }
+        other => panic!("expected to give up, got {:?}", other),
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,65 @@
+use restartables::{CompletionReason, Failure, OutcomeReason, Restartable, StopReason};
+use std::convert::TryFrom;
+use std::time::Duration;
+
+fn is_ok(status: u16) -> Result<(), u16> {
+    if status == 200 {
+        Ok(())
+    } else {
+        Err(status)
+    }
+}
+
+#[tokio::test]
+async fn errors_which_arent_retryable_are_classified_as_such() {
+    let outcome = Restartable::new(|| async { 400 }, Some(Duration::from_secs(60)), is_ok)
+        .retry_if(|status| *status >= 500)
+        .await;
+    assert_eq!(outcome.reason(), CompletionReason::NonRetryable);
+    let failure = outcome.unwrap_err();
+    assert_eq!(failure.reason(), StopReason::NonRetryable);
+    assert!(!failure.is_retryable_later());
+    assert_eq!(
+        failure.to_string(),
+        "gave up after 0 restarts on an error not worth retrying: 400"
+    );
+}
+
+#[tokio::test]
+async fn a_non_retryable_error_on_the_last_attempt_isnt_exhaustion() {
+    let failure = Restartable::retry_n(|| async { 400 }, is_ok, 1)
+        .retry_if(|status| *status >= 500)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        failure,
+        Failure::NonRetryable {
+            error: 400,
+            restarts: 0,
+            ..
+        }
+    ));
+
+    // A retryable error on the last attempt is still exhaustion.
+    let failure = Restartable::retry_n(|| async { 503 }, is_ok, 1)
+        .retry_if(|status| *status >= 500)
+        .await
+        .unwrap_err();
+    assert_eq!(failure.reason(), StopReason::Exhausted);
+    assert!(failure.is_retryable_later());
+}
+
+#[test]
+fn non_retryable_failures_survive_the_round_trip_through_stopped() {
+    let failure = Failure::NonRetryable {
+        error: 400,
+        restarts: 2,
+        elapsed: Duration::ZERO,
+        recent_errors: Vec::new(),
+        dropped_errors: 0,
+    };
+    assert_eq!(
+        Failure::try_from(failure.clone().into_stopped()).unwrap(),
+        failure
+    );
+}
@@ -0,0 +1,124 @@
+//! Deterministic tests of the Restartable's interaction with tokio's timers, using paused time.
+//!
+//! The overall and per-attempt timeouts read the Restartable's `Clock`, not tokio's, so they're
+//! covered in clock.rs instead. Delays between attempts from `gate_next_attempt` and, with the
+//! `use_tokio` feature, `min_retry_interval` use tokio's timers.
+
+use restartables::Restartable;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::Poll;
+use std::time::Duration;
+use tokio::time::{advance, sleep, Instant};
+
+const DELAY: Duration = Duration::from_millis(100);
+
+/// Polls `fut` exactly once, without giving tokio a chance to auto-advance paused time.
+async fn poll_once<F: Future>(fut: Pin<&mut F>) -> Poll<F::Output> {
+    let mut fut = Some(fut);
+    std::future::poll_fn(|cx| Poll::Ready(fut.take().unwrap().poll(cx))).await
+}
+
+#[tokio::test(start_paused = true)]
+async fn advancing_past_the_gate_delay_allows_exactly_one_more_attempt() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let counter = attempts.clone();
+    let retrying = Restartable::new(
+        move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            std::future::ready(())
+        },
+        None,
+        |()| Err::<(), ()>(()),
+    )
+    .gate_next_attempt(|| sleep(DELAY));
+    tokio::pin!(retrying);
+
+    // The first attempt fails straight away, and the second is built but waits for the gate.
+    assert!(poll_once(retrying.as_mut()).await.is_pending());
+    assert!(poll_once(retrying.as_mut()).await.is_pending());
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    for expected in 3..6 {
+        advance(DELAY - Duration::from_millis(1)).await;
+        assert!(poll_once(retrying.as_mut()).await.is_pending());
+        assert_eq!(attempts.load(Ordering::SeqCst), expected - 1);
+
+        advance(Duration::from_millis(1)).await;
+        assert!(poll_once(retrying.as_mut()).await.is_pending());
+        assert_eq!(attempts.load(Ordering::SeqCst), expected);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn gate_delays_add_up_exactly() {
+    let attempts = AtomicUsize::new(0);
+    let start = Instant::now();
+    let success = Restartable::new(
+        || async { attempts.fetch_add(1, Ordering::SeqCst) },
+        None,
+        |n| if n == 3 { Ok(n) } else { Err(n) },
+    )
+    .gate_next_attempt(|| sleep(DELAY))
+    .await
+    .unwrap();
+    assert_eq!(success.restarts, 3);
+    // Paused time only moves when every task is waiting on a timer, so no time passes while the
+    // attempts themselves run.
+    assert_eq!(start.elapsed(), DELAY * 3);
+}
+
+/// Without `use_tokio`, the interval is slept on a real thread, which paused time can't skip.
+#[cfg(feature = "use_tokio")]
+#[tokio::test(start_paused = true)]
+async fn min_retry_intervals_add_up_exactly() {
+    let attempts = AtomicUsize::new(0);
+    let start = Instant::now();
+    let success = Restartable::new(
+        || async { attempts.fetch_add(1, Ordering::SeqCst) },
+        None,
+        |n| if n == 3 { Ok(n) } else { Err(n) },
+    )
+    .min_retry_interval(DELAY)
+    .await
+    .unwrap();
+    assert_eq!(success.restarts, 3);
+    assert_eq!(start.elapsed(), DELAY * 3);
+}
+
+#[cfg(feature = "use_tokio")]
+#[tokio::test(start_paused = true)]
+async fn backoff_delays_add_up_exactly() {
+    let attempts = AtomicUsize::new(0);
+    let start = Instant::now();
+    let success = Restartable::new(
+        || async { attempts.fetch_add(1, Ordering::SeqCst) },
+        None,
+        |n| if n == 3 { Ok(n) } else { Err(n) },
+    )
+    .backoff(DELAY)
+    .min_retry_interval(DELAY / 2)
+    .await
+    .unwrap();
+    assert_eq!(success.restarts, 3);
+    // The backoff is longer than the minimum interval, so it wins.
+    assert_eq!(start.elapsed(), DELAY * 3);
+}
+
+#[cfg(feature = "use_tokio")]
+#[tokio::test(start_paused = true)]
+async fn backoff_sleeps_instead_of_polling_in_a_loop() {
+    let retrying = Restartable::retry_n(|| async { 1 }, Err::<(), i32>, 4).backoff(DELAY);
+    tokio::pin!(retrying);
+    let mut polls = 0;
+    let outcome = std::future::poll_fn(|cx| {
+        polls += 1;
+        retrying.as_mut().poll(cx)
+    })
+    .await;
+    assert!(outcome.is_err());
+    // Each failure wakes the task once, to start the next attempt's sleep, and the sleep wakes
+    // it once more when it's over. Busy-waiting would poll until the delay passed instead.
+    assert_eq!(polls, 7);
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "use_reqwest")]
+
+use restartables::reqw::{RetryingClient, StartError};
+use restartables::Failure;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Serves one response per connection, in order, repeating the last one forever, and counts the
+/// requests it gets.
+async fn serve(responses: Vec<Vec<&'static [u8]>>) -> (reqwest::Url, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    tokio::spawn(async move {
+        for i in 0.. {
+            let parts = &responses[i.min(responses.len() - 1)];
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"\r\n\r\n") {
+                let mut byte = [0];
+                socket.read_exact(&mut byte).await.unwrap();
+                request.push(byte[0]);
+            }
+            counter.fetch_add(1, Ordering::SeqCst);
+            // Send the response in parts, so the body arrives after the headers.
+            for part in parts.iter().copied() {
+                socket.write_all(part).await.unwrap();
+                socket.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    });
+    (reqwest::Url::parse(&url).unwrap(), requests)
+}
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const HTML: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+const DOWNLOAD_HEADERS: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream; x=y\r\nContent-Length: 10\r\nConnection: close\r\n\r\n";
+
+#[tokio::test]
+async fn execute_stream_retries_until_headers_pass_then_stops() {
+    let (url, requests) = serve(vec![
+        vec![UNAVAILABLE],
+        vec![HTML],
+        vec![DOWNLOAD_HEADERS, b"01234", b"56789"],
+    ])
+    .await;
+    let client = RetryingClient::default();
+    let req = reqwest::Request::new(reqwest::Method::GET, url);
+    let success = client
+        .execute_stream(
+            req,
+            Some("application/octet-stream"),
+            Some(Duration::from_secs(5)),
+        )
+        .await
+        .unwrap();
+    assert_eq!(success.restarts, 2);
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    // Streaming the body doesn't make any more requests.
+    let mut resp = success.value;
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await.unwrap() {
+        body.extend_from_slice(&chunk);
+    }
+    assert_eq!(body, b"0123456789");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn execute_stream_reports_the_last_bad_response() {
+    let (url, _) = serve(vec![vec![HTML]]).await;
+    let client = RetryingClient::default();
+    let req = reqwest::Request::new(reqwest::Method::GET, url);
+    // The timeout can expire mid-request, in which case there's no response to report.
+    let outcome = client
+        .execute_stream(
+            req,
+            Some("application/octet-stream"),
+            Some(Duration::from_millis(200)),
+        )
+        .await;
+    match outcome {
+        Err(Failure::Err {
+            error: StartError::ContentType(Some(content_type)),
+            ..
+        }) => assert_eq!(content_type, "text/html"),
+        Err(Failure::Timeout { .. }) => {}
+        other => panic!("expected the wrong content type, got {:?}", other),
+    }
+}
@@ -0,0 +1,83 @@
+use restartables::{Failure, Restartable, RetryState};
+use std::time::Duration;
+
+#[tokio::test]
+async fn resuming_past_the_timeout_gives_up_after_one_attempt() {
+    let saved = RetryState {
+        restarts: 7,
+        elapsed: Duration::from_secs(10),
+        backoff_position: 7,
+    };
+    let retrying = Restartable::resume(
+        saved,
+        || async { 1 },
+        Some(Duration::from_secs(5)),
+        |n: u8| Err::<u8, _>(n),
+    );
+    assert_eq!(retrying.state(), saved);
+    match retrying.await {
+        Err(Failure::Err {
+            error,
+            restarts,
+            elapsed,
+            ..
+        }) => {
+            assert_eq!(error, 1);
+            assert_eq!(restarts, 7);
+            // The saved elapsed time counts, just like it does towards the timeout.
+            assert!(elapsed >= Duration::from_secs(10));
+        }
+        other => panic!("expected the test's error, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "use_serde")]
+#[tokio::test]
+async fn state_survives_a_round_trip_through_json() {
+    use restartables::Backoff;
+    use std::future::Future;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::Poll;
+
+    #[derive(Default, Clone)]
+    struct Recording(Arc<Mutex<Vec<usize>>>);
+
+    impl Backoff for Recording {
+        fn delay(&mut self, restarts: usize) -> Duration {
+            self.0.lock().unwrap().push(restarts);
+            Duration::ZERO
+        }
+    }
+
+    // Passes on the fifth attempt overall.
+    let attempts = AtomicUsize::new(0);
+    let factory = || async { attempts.fetch_add(1, Ordering::SeqCst) };
+    let test = |n: usize| if n == 4 { Ok(n) } else { Err(n) };
+    let timeout = Some(Duration::from_secs(60));
+
+    // Make a few attempts, then save the state, as if the process was about to crash.
+    let backoff = Recording::default();
+    let retrying = Restartable::new(factory, timeout, test).backoff(backoff.clone());
+    tokio::pin!(retrying);
+    for _ in 0..3 {
+        let poll = std::future::poll_fn(|cx| Poll::Ready(retrying.as_mut().poll(cx))).await;
+        assert!(poll.is_pending());
+    }
+    let state = retrying.state();
+    assert_eq!(state.restarts, 3);
+    assert_eq!(state.backoff_position, 3);
+    let saved = serde_json::to_string(&state).unwrap();
+
+    let loaded: RetryState = serde_json::from_str(&saved).unwrap();
+    assert_eq!(loaded, state);
+    let success = Restartable::resume(loaded, factory, timeout, test)
+        .backoff(backoff.clone())
+        .await
+        .unwrap();
+    assert_eq!(success.value, 4);
+    assert_eq!(success.restarts, 4);
+    assert!(success.duration >= state.elapsed);
+    // The resumed loop's backoff carries on from where the first one stopped.
+    assert_eq!(*backoff.0.lock().unwrap(), [0, 1, 2, 3]);
+}
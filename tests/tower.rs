@@ -0,0 +1,46 @@
+#![cfg(feature = "use_tower")]
+
+use restartables::tower::Retry;
+use restartables::{Failure, FixedInterval, RetryPolicy};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower::{service_fn, ServiceExt};
+
+#[tokio::test]
+async fn calls_fail_once_the_policy_gives_up() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let always_down = service_fn(move |_: ()| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        async { Err::<(), _>("service unavailable") }
+    });
+    let policy = RetryPolicy::builder().max_attempts(3).build();
+    let service = Retry::new(always_down, policy, |result: Result<(), &'static str>| {
+        result
+    });
+    match service.oneshot(()).await {
+        Err(Failure::Exhausted { error, restarts }) => {
+            assert_eq!(error, "service unavailable");
+            assert_eq!(restarts, 2);
+        }
+        other => panic!("expected exhaustion, got {:?}", other),
+    }
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn calls_are_retried_after_the_policys_backoff() {
+    let always_down = service_fn(|_: ()| async { Err::<(), _>("service unavailable") });
+    let policy = RetryPolicy::builder()
+        .max_attempts(3)
+        .backoff(FixedInterval(Duration::from_millis(20)))
+        .build();
+    let service = Retry::new(always_down, policy, |result: Result<(), &'static str>| {
+        result
+    });
+    let start = Instant::now();
+    assert!(service.oneshot(()).await.is_err());
+    // Two restarts, each after a backoff.
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}